@@ -0,0 +1,63 @@
+// Compares buffers wrapping their indices with a comparison against the ones created by
+// `new_pow2`, which use a bitmask.
+//
+// Run with `cargo bench`. Under `cargo test`, each case only runs briefly to check that it works.
+use circular_buffer::CircularBuffer;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+type Case = fn(CircularBuffer<u64>, u64) -> Duration;
+type Constructor = fn(usize) -> CircularBuffer<u64>;
+
+fn write_then_read(mut buffer: CircularBuffer<u64>, operations: u64) -> Duration {
+    let start = Instant::now();
+    for i in 0..operations {
+        if buffer.is_full() {
+            black_box(buffer.read().unwrap());
+        }
+        buffer.write(black_box(i)).unwrap();
+    }
+
+    start.elapsed()
+}
+
+fn overwrite(mut buffer: CircularBuffer<u64>, operations: u64) -> Duration {
+    let start = Instant::now();
+    for i in 0..operations {
+        black_box(buffer.overwrite(black_box(i)));
+    }
+
+    start.elapsed()
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`, while `cargo test` runs the benchmarks as smoke tests.
+    let (runs, operations) = if std::env::args().any(|arg| arg == "--bench") {
+        (5, 10_000_000)
+    } else {
+        (1, 10_000)
+    };
+
+    let cases: [(&str, Case); 2] = [
+        ("write then read", write_then_read),
+        ("overwrite", overwrite),
+    ];
+    let constructors: [(&str, Constructor); 2] = [
+        ("new(1000)", CircularBuffer::new),
+        ("new_pow2(1000)", CircularBuffer::new_pow2),
+    ];
+    for (name, case) in cases {
+        for (kind, new) in constructors {
+            let best = (0..runs)
+                .map(|_| case(new(1000), operations))
+                .min()
+                .unwrap();
+            println!(
+                "{:<16} {:<15} {:>6.2} ns/op",
+                name,
+                kind,
+                best.as_nanos() as f64 / operations as f64
+            );
+        }
+    }
+}
@@ -0,0 +1,22 @@
+/*
+ * C declarations of the functions exported by the `ffi` feature of circular-buffer.
+ *
+ * Build a static library to link against with:
+ *     cargo rustc --release --features ffi --crate-type staticlib
+ *
+ * Functions returning an int return 0 on success and -1 if the buffer was full or empty.
+ */
+#ifndef CIRCULAR_BUFFER_H
+#define CIRCULAR_BUFFER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct CircularBuffer CircularBuffer;
+
+CircularBuffer *cb_new(size_t capacity);
+int cb_write(CircularBuffer *buffer, uint8_t byte);
+int cb_read(CircularBuffer *buffer, uint8_t *out);
+void cb_free(CircularBuffer *buffer);
+
+#endif
@@ -0,0 +1,79 @@
+use crate::{CircularBuffer, OverflowPolicy, Stats};
+use core::mem::MaybeUninit;
+
+// A CircularBuffer with a capacity fixed at compile time, stored inline without allocation.
+//
+// It has the whole API of the Vec-backed buffer, except for what needs to allocate:
+// the constructors taking a capacity, `resize`, `shrink_to_fit`, `split_off`, `split`
+// and snapshots. A growing overflow policy behaves like `Reject`.
+pub type ArrayCircularBuffer<T, const N: usize> = CircularBuffer<T, [MaybeUninit<T>; N]>;
+
+impl<T, const N: usize> CircularBuffer<T, [MaybeUninit<T>; N]> {
+    // Construct an empty buffer with the capacity of the array. Unlike `from_storage`,
+    // this can be used to initialize a `static` or a `const`.
+    pub const fn new_array() -> Self {
+        Self {
+            buffer: [const { MaybeUninit::uninit() }; N],
+            start_idx: 0,
+            len: 0,
+            pow2: N.is_power_of_two(),
+            overflow_policy: OverflowPolicy::Reject,
+            stats: Stats {
+                writes: 0,
+                reads: 0,
+                evictions: 0,
+                failed_writes: 0,
+                high_watermark: 0,
+            },
+            evict_hook: None,
+        }
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for CircularBuffer<T, [MaybeUninit<T>; N]> {
+    fn clone(&self) -> Self {
+        self.clone_elements_into(Self::new_array())
+    }
+}
+
+impl<T, const N: usize> Default for CircularBuffer<T, [MaybeUninit<T>; N]> {
+    fn default() -> Self {
+        Self::new_array()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_can_be_built_at_compile_time() {
+        const EMPTY: ArrayCircularBuffer<u8, 4> = ArrayCircularBuffer::new_array();
+        let mut buffer = EMPTY;
+        assert_eq!(4, buffer.capacity());
+        assert_eq!(4, buffer.write_slice(b"abcd"));
+        assert_eq!(Ok(b'a'), buffer.read());
+    }
+
+    #[test]
+    fn default_buffer_is_empty_with_the_array_capacity() {
+        let buffer = ArrayCircularBuffer::<char, 3>::default();
+        assert!(buffer.is_empty());
+        assert_eq!(3, buffer.capacity());
+        assert_eq!(OverflowPolicy::Reject, buffer.overflow_policy());
+    }
+
+    #[test]
+    fn clone_copies_the_elements_into_a_new_array() {
+        let mut buffer = ArrayCircularBuffer::<String, 2>::new_array();
+        assert!(buffer.write("1".to_string()).is_ok());
+        assert!(buffer.write("2".to_string()).is_ok());
+        assert_eq!(Ok("1".to_string()), buffer.read());
+
+        let mut copy = buffer.clone();
+        assert!(copy.write("3".to_string()).is_ok());
+        assert_eq!(vec!["2"], buffer.to_vec());
+        assert_eq!(vec!["2", "3"], copy.to_vec());
+        assert_eq!(buffer.stats(), ArrayCircularBuffer::clone(&buffer).stats());
+    }
+}
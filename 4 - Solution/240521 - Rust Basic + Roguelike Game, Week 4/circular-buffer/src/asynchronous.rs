@@ -0,0 +1,250 @@
+// A thread-safe CircularBuffer whose readers and writers can `.await` elements and free slots.
+//
+// It only relies on `std::task`, so it works with any executor.
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+pub struct CircularBuffer<T> {
+    state: Mutex<State<T>>,
+}
+
+struct State<T> {
+    buffer: crate::CircularBuffer<T>,
+    // Tasks waiting for an element to be written.
+    readers: Vec<Waker>,
+    // Tasks waiting for a slot to be freed.
+    writers: Vec<Waker>,
+}
+
+// Register the waker of a task, unless it is already waiting.
+fn register(wakers: &mut Vec<Waker>, waker: &Waker) {
+    if !wakers.iter().any(|w| w.will_wake(waker)) {
+        wakers.push(waker.clone());
+    }
+}
+
+impl<T> CircularBuffer<T> {
+    // A buffer with a capacity of 0 cannot store anything, so `poll_read` and `poll_write`
+    // always return `Poll::Pending`, and the futures of `read` and `write` never complete.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(State {
+                buffer: crate::CircularBuffer::new(capacity),
+                readers: Vec::new(),
+                writers: Vec::new(),
+            }),
+        }
+    }
+
+    // Read the oldest element, or register the task to be woken once one is written.
+    pub fn poll_read(&self, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.lock();
+        match state.buffer.read() {
+            Ok(element) => {
+                let writers = std::mem::take(&mut state.writers);
+                drop(state);
+                writers.into_iter().for_each(Waker::wake);
+
+                Poll::Ready(element)
+            }
+            Err(_) => {
+                register(&mut state.readers, cx.waker());
+                Poll::Pending
+            }
+        }
+    }
+
+    // Move the element out of `element` into the buffer, or leave it there and register
+    // the task to be woken once a slot is freed.
+    pub fn poll_write(&self, cx: &mut Context<'_>, element: &mut Option<T>) -> Poll<()> {
+        let Some(value) = element.take() else {
+            return Poll::Ready(());
+        };
+
+        let mut state = self.lock();
+        match state.buffer.write(value) {
+            Ok(()) => {
+                let readers = std::mem::take(&mut state.readers);
+                drop(state);
+                readers.into_iter().for_each(Waker::wake);
+
+                Poll::Ready(())
+            }
+            Err(rejected) => {
+                *element = Some(rejected.into_inner());
+                register(&mut state.writers, cx.waker());
+                Poll::Pending
+            }
+        }
+    }
+
+    // Wait until an element is available and read it.
+    pub fn read(&self) -> Read<'_, T> {
+        Read { buffer: self }
+    }
+
+    // Wait until a slot is free and write the element into it.
+    pub fn write(&self, element: T) -> Write<'_, T> {
+        Write {
+            buffer: self,
+            element: Some(element),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().buffer.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lock().buffer.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().buffer.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.lock().buffer.is_full()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        crate::lock(&self.state)
+    }
+}
+
+// The future returned by `CircularBuffer::read`.
+pub struct Read<'a, T> {
+    buffer: &'a CircularBuffer<T>,
+}
+
+impl<T> Future for Read<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.buffer.poll_read(cx)
+    }
+}
+
+// The future returned by `CircularBuffer::write`.
+pub struct Write<'a, T> {
+    buffer: &'a CircularBuffer<T>,
+    element: Option<T>,
+}
+
+// The element is never pinned, so the future can be moved freely.
+impl<T> Unpin for Write<'_, T> {}
+
+impl<T> Future for Write<'_, T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        this.buffer.poll_write(cx, &mut this.element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Wake;
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    // Run the future to completion on the current thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn pending_read_is_woken_by_a_write() {
+        let buffer = CircularBuffer::new(1);
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(Poll::Pending, buffer.poll_read(&mut cx));
+        assert_eq!(Poll::Pending, buffer.poll_read(&mut cx));
+        assert_eq!(0, counter.0.load(Ordering::SeqCst));
+        block_on(buffer.write('1'));
+        assert_eq!(1, counter.0.load(Ordering::SeqCst));
+        assert_eq!(Poll::Ready('1'), buffer.poll_read(&mut cx));
+    }
+
+    #[test]
+    fn pending_write_keeps_the_item() {
+        let buffer = CircularBuffer::new(1);
+        let waker = Waker::from(Arc::new(CountingWaker(AtomicUsize::new(0))));
+        let mut cx = Context::from_waker(&waker);
+
+        let mut element = Some('1');
+        assert_eq!(Poll::Ready(()), buffer.poll_write(&mut cx, &mut element));
+        assert_eq!(None, element);
+        let mut element = Some('2');
+        assert_eq!(Poll::Pending, buffer.poll_write(&mut cx, &mut element));
+        assert_eq!(Some('2'), element);
+    }
+
+    #[test]
+    fn readers_and_writers_await_each_other() {
+        let buffer = Arc::new(CircularBuffer::new(2));
+
+        let writer = {
+            let buffer = Arc::clone(&buffer);
+            thread::spawn(move || {
+                block_on(async {
+                    for i in 0..100 {
+                        buffer.write(i).await;
+                    }
+                })
+            })
+        };
+
+        block_on(async {
+            for i in 0..100 {
+                assert_eq!(i, buffer.read().await);
+            }
+        });
+
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn zero_capacity_buffer_stays_pending() {
+        let buffer = CircularBuffer::new(0);
+        let waker = Waker::from(Arc::new(CountingWaker(AtomicUsize::new(0))));
+        let mut cx = Context::from_waker(&waker);
+
+        let mut element = Some('1');
+        assert_eq!(Poll::Pending, buffer.poll_write(&mut cx, &mut element));
+        assert_eq!(Some('1'), element);
+        assert_eq!(Poll::Pending, buffer.poll_read(&mut cx));
+    }
+}
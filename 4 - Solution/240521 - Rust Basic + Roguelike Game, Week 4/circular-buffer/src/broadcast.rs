@@ -0,0 +1,191 @@
+// A CircularBuffer whose elements are seen by every Reader, each reading at its own pace.
+//
+// Writes never wait for the readers: the oldest element is overwritten when the buffer is full,
+// and a reader that had not read it yet is told how many elements it missed.
+use crate::lock;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, PartialEq)]
+pub enum ReadError {
+    // The reader has already seen every element written so far.
+    Empty,
+    // The reader fell behind and missed this many elements.
+    Lagged(u64),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Empty => write!(f, "no new element in the broadcast buffer"),
+            ReadError::Lagged(missed) => write!(f, "a broadcast reader missed {} elements", missed),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+struct Shared<T> {
+    buffer: crate::CircularBuffer<T>,
+    // The number of elements ever written, which is the sequence number of the next one.
+    written: u64,
+}
+
+impl<T> Shared<T> {
+    // The sequence number of the oldest element still in the buffer.
+    fn start_seq(&self) -> u64 {
+        self.written - self.buffer.len() as u64
+    }
+}
+
+pub struct BroadcastBuffer<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> BroadcastBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(Shared {
+                buffer: crate::CircularBuffer::new(capacity),
+                written: 0,
+            })),
+        }
+    }
+
+    // Create a Reader that sees every element written from now on.
+    pub fn subscribe(&self) -> Reader<T> {
+        Reader {
+            shared: Arc::clone(&self.shared),
+            next_seq: lock(&self.shared).written,
+        }
+    }
+
+    // Write the element, overwriting the oldest one if the buffer is full.
+    pub fn write(&self, element: T) {
+        let mut shared = lock(&self.shared);
+        shared.buffer.overwrite(element);
+        shared.written += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        lock(&self.shared).buffer.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        lock(&self.shared).buffer.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        lock(&self.shared).buffer.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        lock(&self.shared).buffer.is_full()
+    }
+}
+
+// A cursor over the elements of a BroadcastBuffer, which can be sent to another thread.
+pub struct Reader<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    // The sequence number of the next element to read.
+    next_seq: u64,
+}
+
+impl<T> Reader<T> {
+    // Read a clone of the oldest element this reader has not seen yet.
+    //
+    // If elements were overwritten before this reader got to them, `ReadError::Lagged` tells how
+    // many were missed, and the next read continues from the oldest element still stored.
+    pub fn read(&mut self) -> Result<T, ReadError>
+    where
+        T: Clone,
+    {
+        let shared = lock(&self.shared);
+        let start_seq = shared.start_seq();
+        if self.next_seq < start_seq {
+            let missed = start_seq - self.next_seq;
+            self.next_seq = start_seq;
+            return Err(ReadError::Lagged(missed));
+        }
+
+        let element = shared
+            .buffer
+            .get((self.next_seq - start_seq) as usize)
+            .ok_or(ReadError::Empty)?
+            .clone();
+        self.next_seq += 1;
+
+        Ok(element)
+    }
+
+    // Return the number of stored elements this reader has not seen yet.
+    pub fn len(&self) -> usize {
+        let shared = lock(&self.shared);
+        (shared.written - self.next_seq.max(shared.start_seq())) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn every_reader_sees_every_element() {
+        let buffer = BroadcastBuffer::new(3);
+        let mut first = buffer.subscribe();
+        let mut second = buffer.subscribe();
+        buffer.write('1');
+        buffer.write('2');
+
+        assert_eq!(Ok('1'), first.read());
+        assert_eq!(Ok('2'), first.read());
+        assert_eq!(Err(ReadError::Empty), first.read());
+        assert_eq!(2, second.len());
+        assert_eq!(Ok('1'), second.read());
+        buffer.write('3');
+        assert_eq!(Ok('2'), second.read());
+        assert_eq!(Ok('3'), second.read());
+        assert_eq!(Ok('3'), first.read());
+    }
+
+    #[test]
+    fn slow_reader_lags_and_skips_to_the_oldest_element() {
+        let buffer = BroadcastBuffer::new(2);
+        let mut reader = buffer.subscribe();
+        for i in 0..5 {
+            buffer.write(i);
+        }
+
+        assert_eq!(2, reader.len());
+        assert_eq!(Err(ReadError::Lagged(3)), reader.read());
+        assert_eq!(Ok(3), reader.read());
+        assert_eq!(Ok(4), reader.read());
+        assert!(reader.is_empty());
+
+        let mut late = buffer.subscribe();
+        assert_eq!(Err(ReadError::Empty), late.read());
+    }
+
+    #[test]
+    fn readers_can_be_moved_to_other_threads() {
+        let buffer = BroadcastBuffer::new(100);
+        let readers: Vec<_> = (0..3).map(|_| buffer.subscribe()).collect();
+        for i in 0..100 {
+            buffer.write(i);
+        }
+
+        let handles: Vec<_> = readers
+            .into_iter()
+            .map(|mut reader| thread::spawn(move || (0..100).map(|_| reader.read()).collect()))
+            .collect();
+        for handle in handles {
+            let read: Vec<_> = handle.join().unwrap();
+            assert_eq!((0..100).map(Ok).collect::<Vec<_>>(), read);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// The two halves of a CircularBuffer split by `CircularBuffer::split`, like the ends of a channel.
+//
+// With the `std` feature, the halves share the buffer through a Mutex and can be sent to other
+// threads. Without it, they share it through a RefCell and stay on the same thread.
+use crate::{CircularBuffer, Error, WriteError};
+
+#[cfg(feature = "std")]
+type Shared<T> = std::sync::Arc<std::sync::Mutex<CircularBuffer<T>>>;
+#[cfg(not(feature = "std"))]
+type Shared<T> = alloc::rc::Rc<core::cell::RefCell<CircularBuffer<T>>>;
+
+pub(crate) fn split<T>(buffer: CircularBuffer<T>) -> (Sender<T>, Receiver<T>) {
+    let shared = Shared::new(buffer.into());
+
+    (
+        Sender {
+            shared: Shared::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+// Run `f` on the shared buffer.
+#[cfg(feature = "std")]
+fn with<T, R>(shared: &Shared<T>, f: impl FnOnce(&mut CircularBuffer<T>) -> R) -> R {
+    f(&mut crate::lock(shared))
+}
+
+#[cfg(not(feature = "std"))]
+fn with<T, R>(shared: &Shared<T>, f: impl FnOnce(&mut CircularBuffer<T>) -> R) -> R {
+    f(&mut shared.borrow_mut())
+}
+
+// The writing half of a split CircularBuffer.
+pub struct Sender<T> {
+    shared: Shared<T>,
+}
+
+impl<T> Sender<T> {
+    pub fn write(&mut self, element: T) -> Result<(), WriteError<T>> {
+        with(&self.shared, |buffer| buffer.write(element))
+    }
+
+    // Write the element according to the overflow policy of the buffer, like `CircularBuffer::push`.
+    pub fn push(&mut self, element: T) -> Result<Option<T>, WriteError<T>> {
+        with(&self.shared, |buffer| buffer.push(element))
+    }
+
+    pub fn len(&self) -> usize {
+        with(&self.shared, |buffer| buffer.len())
+    }
+
+    pub fn capacity(&self) -> usize {
+        with(&self.shared, |buffer| buffer.capacity())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        with(&self.shared, |buffer| buffer.is_empty())
+    }
+
+    pub fn is_full(&self) -> bool {
+        with(&self.shared, |buffer| buffer.is_full())
+    }
+}
+
+// The reading half of a split CircularBuffer.
+pub struct Receiver<T> {
+    shared: Shared<T>,
+}
+
+impl<T> Receiver<T> {
+    pub fn read(&mut self) -> Result<T, Error> {
+        with(&self.shared, |buffer| buffer.read())
+    }
+
+    pub fn len(&self) -> usize {
+        with(&self.shared, |buffer| buffer.len())
+    }
+
+    pub fn capacity(&self) -> usize {
+        with(&self.shared, |buffer| buffer.capacity())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        with(&self.shared, |buffer| buffer.is_empty())
+    }
+
+    pub fn is_full(&self) -> bool {
+        with(&self.shared, |buffer| buffer.is_full())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receiver_reads_what_the_sender_wrote() {
+        let (mut sender, mut receiver) = CircularBuffer::new(2).split();
+        assert!(sender.write('1').is_ok());
+        assert!(sender.write('2').is_ok());
+        assert!(receiver.is_full());
+        assert_eq!(Err(WriteError('3')), sender.write('3'));
+        assert_eq!(Ok('1'), receiver.read());
+        assert_eq!(1, sender.len());
+        assert_eq!(Ok('2'), receiver.read());
+        assert_eq!(Err(Error::EmptyBuffer), receiver.read());
+    }
+
+    #[test]
+    fn sender_keeps_the_overflow_policy() {
+        let buffer = CircularBuffer::with_overflow_policy(1, crate::OverflowPolicy::Overwrite);
+        let (mut sender, mut receiver) = buffer.split();
+        assert_eq!(Ok(None), sender.push('1'));
+        assert_eq!(Ok(Some('1')), sender.push('2'));
+        assert_eq!(Ok('2'), receiver.read());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn halves_can_be_moved_to_other_threads() {
+        let (mut sender, mut receiver) = CircularBuffer::new(4).split();
+
+        let writer = std::thread::spawn(move || {
+            for i in 0..100 {
+                let mut element = i;
+                while let Err(WriteError(rejected)) = sender.write(element) {
+                    element = rejected;
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < 100 {
+            match receiver.read() {
+                Ok(element) => {
+                    assert_eq!(expected, element);
+                    expected += 1;
+                }
+                Err(_) => std::thread::yield_now(),
+            }
+        }
+
+        writer.join().unwrap();
+    }
+}
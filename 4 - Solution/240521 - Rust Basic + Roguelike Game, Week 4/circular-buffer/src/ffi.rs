@@ -0,0 +1,80 @@
+// C bindings for byte buffers. The matching declarations are in `include/circular_buffer.h`.
+//
+// Functions returning an `int` return 0 on success and -1 if the buffer was full or empty.
+use crate::CircularBuffer;
+use alloc::boxed::Box;
+use core::ffi::c_int;
+
+/// Create a buffer holding up to `capacity` bytes, to be freed with `cb_free`.
+#[no_mangle]
+pub extern "C" fn cb_new(capacity: usize) -> *mut CircularBuffer<u8> {
+    Box::into_raw(Box::new(CircularBuffer::new(capacity)))
+}
+
+/// Write `byte` into the buffer, failing if it is full.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by `cb_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cb_write(buffer: *mut CircularBuffer<u8>, byte: u8) -> c_int {
+    // SAFETY: the caller passes a live buffer created by `cb_new`.
+    match unsafe { &mut *buffer }.write(byte) {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Read the oldest byte into `*out`, failing if the buffer is empty.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by `cb_new` and not freed yet, and `out` must be
+/// valid for writing a byte.
+#[no_mangle]
+pub unsafe extern "C" fn cb_read(buffer: *mut CircularBuffer<u8>, out: *mut u8) -> c_int {
+    // SAFETY: the caller passes a live buffer created by `cb_new`.
+    match unsafe { &mut *buffer }.read() {
+        Ok(byte) => {
+            // SAFETY: the caller passes a pointer valid for writing a byte.
+            unsafe { out.write(byte) };
+            0
+        }
+        Err(_) => -1,
+    }
+}
+
+/// Free the buffer and the bytes it still holds. Passing NULL does nothing.
+///
+/// # Safety
+///
+/// `buffer` must be NULL, or have been returned by `cb_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cb_free(buffer: *mut CircularBuffer<u8>) {
+    if !buffer.is_null() {
+        // SAFETY: the buffer was allocated by `cb_new` and is not used after this.
+        drop(unsafe { Box::from_raw(buffer) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_go_through_the_c_functions() {
+        let buffer = cb_new(2);
+        let mut byte = 0;
+        // SAFETY: the buffer is freed at the end, and `byte` is a valid destination.
+        unsafe {
+            assert_eq!(-1, cb_read(buffer, &mut byte));
+            assert_eq!(0, cb_write(buffer, b'a'));
+            assert_eq!(0, cb_write(buffer, b'b'));
+            assert_eq!(-1, cb_write(buffer, b'c'));
+            assert_eq!(0, cb_read(buffer, &mut byte));
+            assert_eq!(b'a', byte);
+            cb_free(buffer);
+            cb_free(core::ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,318 @@
+// A byte CircularBuffer stored in a file, so that its contents survive process restarts.
+//
+// The standard library cannot map files into memory, so the bytes are accessed with
+// positioned reads and writes instead. The file starts with a small header holding the
+// capacity and the read position, followed by the ring itself.
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const MAGIC: [u8; 8] = *b"CIRCBUF1";
+const HEADER_LEN: u64 = 32;
+
+pub struct CircularBuffer {
+    file: File,
+    capacity: usize,
+    start_idx: usize,
+    len: usize,
+    // Whether bytes were read or cleared since the header was last written. The file still
+    // counts them as stored, so their slots must not be reused before the header is updated.
+    freed_since_flush: bool,
+}
+
+impl CircularBuffer {
+    // Open the buffer stored at `path`, creating an empty one if the file does not exist.
+    //
+    // An existing file must have been created with the same capacity.
+    pub fn open(path: impl AsRef<Path>, capacity: usize) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        if file.metadata()?.len() == 0 {
+            file.set_len(HEADER_LEN + capacity as u64)?;
+            let mut buffer = Self {
+                file,
+                capacity,
+                start_idx: 0,
+                len: 0,
+                freed_since_flush: false,
+            };
+            buffer.flush()?;
+            return Ok(buffer);
+        }
+
+        let mut header = [0; HEADER_LEN as usize];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+        if header[..8] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a circular buffer file",
+            ));
+        }
+
+        let field = |i: usize| u64::from_le_bytes(header[i..i + 8].try_into().unwrap()) as usize;
+        let (stored_capacity, start_idx, len) = (field(8), field(16), field(24));
+        if stored_capacity != capacity {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "the file holds a circular buffer of capacity {}, not {}",
+                    stored_capacity, capacity
+                ),
+            ));
+        }
+        if len > capacity || start_idx >= capacity.max(1) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "corrupted circular buffer header",
+            ));
+        }
+        if file.metadata()?.len() < HEADER_LEN + capacity as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated circular buffer file",
+            ));
+        }
+
+        Ok(Self {
+            file,
+            capacity,
+            start_idx,
+            len,
+            freed_since_flush: false,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity
+    }
+
+    // Forget every stored byte. It is persisted by the next flush, or by the next write.
+    pub fn clear(&mut self) {
+        self.start_idx = 0;
+        self.len = 0;
+        self.freed_since_flush = true;
+    }
+
+    // Flush the buffer and ask the operating system to persist the file on the disk,
+    // so that its contents also survive a power loss.
+    pub fn sync_all(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.file.sync_all()
+    }
+
+    // Seek to each of the one or two segments of `count` bytes starting at `idx` in the ring,
+    // which wrap around its end, and run `f` with the length of the segment.
+    fn for_segments(
+        &mut self,
+        idx: usize,
+        count: usize,
+        mut f: impl FnMut(&mut File, usize) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let head_len = count.min(self.capacity - idx);
+        for (offset, len) in [(idx, head_len), (0, count - head_len)] {
+            if len > 0 {
+                self.file
+                    .seek(SeekFrom::Start(HEADER_LEN + offset as u64))?;
+                f(&mut self.file, len)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Write the header describing the current contents, without flushing the file.
+    fn write_header(&mut self) -> io::Result<()> {
+        let mut header = [0; HEADER_LEN as usize];
+        header[..8].copy_from_slice(&MAGIC);
+        header[8..16].copy_from_slice(&(self.capacity as u64).to_le_bytes());
+        header[16..24].copy_from_slice(&(self.start_idx as u64).to_le_bytes());
+        header[24..32].copy_from_slice(&(self.len as u64).to_le_bytes());
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.freed_since_flush = false;
+
+        Ok(())
+    }
+}
+
+// Reading copies the oldest bytes out of the file and only moves the read position in memory.
+// The file keeps counting those bytes as stored until the header is written again.
+impl Read for CircularBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = buf.len().min(self.len);
+        let mut done = 0;
+        self.for_segments(self.start_idx, count, |file, len| {
+            file.read_exact(&mut buf[done..done + len])?;
+            done += len;
+            Ok(())
+        })?;
+
+        if count > 0 {
+            self.start_idx = (self.start_idx + count) % self.capacity;
+            self.len -= count;
+            self.freed_since_flush = true;
+        }
+
+        Ok(count)
+    }
+}
+
+// Writing puts the bytes into the file right away, but the header only counts them once
+// `flush` writes it. After a read or a clear, the header is written before the new bytes,
+// so that they never overwrite bytes it still counts as stored: a crash before the next
+// flush only loses the new bytes.
+impl Write for CircularBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = buf.len().min(self.capacity - self.len);
+        if count == 0 {
+            return Ok(0);
+        }
+
+        if self.freed_since_flush {
+            self.write_header()?;
+        }
+
+        let end_idx = (self.start_idx + self.len) % self.capacity;
+        let mut done = 0;
+        self.for_segments(end_idx, count, |file, len| {
+            file.write_all(&buf[done..done + len])?;
+            done += len;
+            Ok(())
+        })?;
+        self.len += count;
+
+        Ok(count)
+    }
+
+    // Write the header, persisting the current contents across process restarts.
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_header()?;
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // A file in the temporary directory that is removed at the end of the test.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "circular-buffer-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = std::fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn contents_survive_reopening_after_a_flush() {
+        let path = TempFile::new("reopen");
+
+        let mut buffer = CircularBuffer::open(&path.0, 4).unwrap();
+        buffer.write_all(b"abcd").unwrap();
+        let mut out = [0; 2];
+        buffer.read_exact(&mut out).unwrap();
+        assert_eq!(b"ab", &out);
+        buffer.write_all(b"ef").unwrap();
+        buffer.flush().unwrap();
+        drop(buffer);
+
+        let mut buffer = CircularBuffer::open(&path.0, 4).unwrap();
+        assert!(buffer.is_full());
+        let mut out = Vec::new();
+        buffer.read_to_end(&mut out).unwrap();
+        assert_eq!(b"cdef", out.as_slice());
+    }
+
+    #[test]
+    fn unflushed_changes_are_lost() {
+        let path = TempFile::new("unflushed");
+
+        let mut buffer = CircularBuffer::open(&path.0, 4).unwrap();
+        buffer.write_all(b"ab").unwrap();
+        buffer.sync_all().unwrap();
+        buffer.write_all(b"cd").unwrap();
+        drop(buffer);
+
+        let buffer = CircularBuffer::open(&path.0, 4).unwrap();
+        assert_eq!(2, buffer.len());
+    }
+
+    #[test]
+    fn writing_after_an_unflushed_read_keeps_the_file_consistent() {
+        let path = TempFile::new("crash");
+
+        let mut buffer = CircularBuffer::open(&path.0, 4).unwrap();
+        buffer.write_all(b"abcd").unwrap();
+        buffer.flush().unwrap();
+        let mut out = [0; 2];
+        buffer.read_exact(&mut out).unwrap();
+        buffer.write_all(b"ef").unwrap();
+        drop(buffer);
+
+        let mut buffer = CircularBuffer::open(&path.0, 4).unwrap();
+        let mut out = Vec::new();
+        buffer.read_to_end(&mut out).unwrap();
+        assert_eq!(b"cd", out.as_slice());
+    }
+
+    #[test]
+    fn opening_a_corrupted_file_fails() {
+        let path = TempFile::new("corrupted");
+
+        drop(CircularBuffer::open(&path.0, 0).unwrap());
+        let mut file = OpenOptions::new().write(true).open(&path.0).unwrap();
+        file.seek(SeekFrom::Start(16)).unwrap();
+        file.write_all(&5u64.to_le_bytes()).unwrap();
+        let error = CircularBuffer::open(&path.0, 0).err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+
+        std::fs::remove_file(&path.0).unwrap();
+        drop(CircularBuffer::open(&path.0, 4).unwrap());
+        file = OpenOptions::new().write(true).open(&path.0).unwrap();
+        file.set_len(HEADER_LEN + 2).unwrap();
+        let error = CircularBuffer::open(&path.0, 4).err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn opening_with_another_capacity_fails() {
+        let path = TempFile::new("capacity");
+
+        drop(CircularBuffer::open(&path.0, 4).unwrap());
+        let error = CircularBuffer::open(&path.0, 8).err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+    }
+}
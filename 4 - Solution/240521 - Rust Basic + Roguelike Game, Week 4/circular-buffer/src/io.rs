@@ -0,0 +1,104 @@
+// The inherent `read` and `write` methods take precedence over these, so call them
+// through the traits, e.g. `io::Write::write(&mut buffer, bytes)`, or use the provided
+// methods such as `write_all` and `read_exact`.
+use crate::{CircularBuffer, Storage};
+use std::io;
+
+// Reading drains the oldest bytes into the caller's slice.
+impl<S: Storage<u8>> io::Read for CircularBuffer<u8, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.read_into(buf))
+    }
+}
+
+// `fill_buf` returns the oldest bytes up to the wrap-around without copying them.
+impl<S: Storage<u8>> io::BufRead for CircularBuffer<u8, S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.as_slices().0)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = amt.min(self.len);
+        self.start_idx = self.physical_idx(amt);
+        self.len -= amt;
+        self.stats.reads += amt as u64;
+    }
+}
+
+// Writing appends as many bytes as fit and never blocks, so a full buffer accepts 0 bytes.
+impl<S: Storage<u8>> io::Write for CircularBuffer<u8, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(self.write_slice(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, Read, Write};
+
+    #[test]
+    fn write_accepts_up_to_the_remaining_capacity() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(3, Write::write(&mut buffer, b"abc").unwrap());
+        assert_eq!(1, Write::write(&mut buffer, b"de").unwrap());
+        assert_eq!(0, Write::write(&mut buffer, b"f").unwrap());
+        assert!(buffer.write_all(b"f").is_err());
+    }
+
+    #[test]
+    fn read_drains_across_the_wrap_around() {
+        let mut buffer = CircularBuffer::new(4);
+        buffer.write_all(b"abcd").unwrap();
+        let mut out = [0; 3];
+        assert_eq!(3, Read::read(&mut buffer, &mut out).unwrap());
+        assert_eq!(b"abc", &out);
+        buffer.write_all(b"efg").unwrap();
+        let mut out = Vec::new();
+        assert_eq!(4, buffer.read_to_end(&mut out).unwrap());
+        assert_eq!(b"defg", out.as_slice());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn fill_buf_returns_the_bytes_up_to_the_wrap_around() {
+        let mut buffer = CircularBuffer::new(4);
+        buffer.write_all(b"abcd").unwrap();
+        buffer.consume(2);
+        buffer.write_all(b"ef").unwrap();
+        assert_eq!(b"cd", buffer.fill_buf().unwrap());
+        buffer.consume(1);
+        assert_eq!(b"d", buffer.fill_buf().unwrap());
+        buffer.consume(1);
+        assert_eq!(b"ef", buffer.fill_buf().unwrap());
+        buffer.consume(5);
+        assert!(buffer.fill_buf().unwrap().is_empty());
+    }
+
+    #[test]
+    fn buffer_can_be_read_line_by_line() {
+        let mut buffer = CircularBuffer::new(8);
+        buffer.write_all(b"ab\ncd\n").unwrap();
+        let mut line = String::new();
+        assert_eq!(3, buffer.read_line(&mut line).unwrap());
+        assert_eq!("ab\n", line);
+        buffer.write_all(b"e").unwrap();
+        assert_eq!(
+            vec!["cd".to_string(), "e".to_string()],
+            buffer.lines().map(Result::unwrap).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn zero_capacity_buffer_reads_and_writes_nothing() {
+        let mut buffer = CircularBuffer::new(0);
+        assert_eq!(0, Write::write(&mut buffer, b"a").unwrap());
+        assert_eq!(0, Read::read(&mut buffer, &mut [0; 1]).unwrap());
+        buffer.consume(1);
+        assert!(buffer.fill_buf().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,2357 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod array;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+#[cfg(feature = "std")]
+pub mod broadcast;
+pub mod channel;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod file;
+#[cfg(feature = "std")]
+mod io;
+mod seq;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(target_has_atomic = "ptr")]
+pub mod spsc;
+mod storage;
+#[cfg(feature = "std")]
+pub mod sync;
+#[cfg(feature = "std")]
+mod ttl;
+
+pub use array::ArrayCircularBuffer;
+pub use seq::SeqBuffer;
+#[cfg(feature = "std")]
+pub use shared::SharedCircularBuffer;
+pub use storage::Storage;
+#[cfg(feature = "std")]
+pub use ttl::TtlBuffer;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::{FusedIterator, Sum};
+use core::mem::MaybeUninit;
+use core::ops::{Index, IndexMut};
+use core::slice;
+#[cfg(test)]
+use std::rc::Rc;
+
+// Lock the mutex shared by the handles of a thread-safe buffer.
+//
+// A panic unwinding through a method of CircularBuffer, for example from a closure passed
+// to `retain`, may leave the change it was making half done, but never leaves the buffer
+// unsafe to use. So a lock poisoned by a panicking thread is used as is, instead of making
+// every other thread panic too.
+#[cfg(feature = "std")]
+pub(crate) fn lock<T>(mutex: &std::sync::Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+pub struct CircularBuffer<T, S: Storage<T> = Vec<MaybeUninit<T>>> {
+    // Only the `len` slots starting at `start_idx`, wrapping around, are initialized.
+    buffer: S,
+    start_idx: usize,
+    len: usize,
+    // Whether the capacity is a power of two, so that indices can be wrapped with a bitmask.
+    pow2: bool,
+    overflow_policy: OverflowPolicy,
+    stats: Stats,
+    // Receives the elements evicted by overwrites instead of the caller.
+    evict_hook: Option<Box<dyn FnMut(T) + Send + Sync>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    EmptyBuffer,
+    FullBuffer,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::EmptyBuffer => write!(f, "cannot read from an empty circular buffer"),
+            Error::FullBuffer => write!(f, "cannot write to a full circular buffer"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+// A failed write into a full buffer, giving the rejected element back to the caller.
+#[derive(Debug, PartialEq)]
+pub struct WriteError<T>(pub T);
+
+impl<T> WriteError<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Display for WriteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&Error::FullBuffer, f)
+    }
+}
+
+impl<T: fmt::Debug> core::error::Error for WriteError<T> {}
+
+impl<T> From<WriteError<T>> for Error {
+    fn from(_: WriteError<T>) -> Self {
+        Error::FullBuffer
+    }
+}
+
+// Counters of what happened to a CircularBuffer since it was created or `reset_stats` was called.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    // Elements stored by writes and overwrites.
+    pub writes: u64,
+    // Elements removed by reads, from either end.
+    pub reads: u64,
+    // Elements dropped by overwrites to make room.
+    pub evictions: u64,
+    // Elements rejected because the buffer was full.
+    pub failed_writes: u64,
+    // The largest number of elements stored at once.
+    pub high_watermark: usize,
+}
+
+// What `push` and bulk insertions such as `extend` do when the buffer becomes full.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    // Stop inserting and drop the remaining elements.
+    #[default]
+    Reject,
+    // Keep inserting, overwriting the oldest elements.
+    Overwrite,
+    // Keep inserting, doubling the capacity of the buffer. `write` also grows instead of failing.
+    Grow,
+}
+
+impl<T> CircularBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        let mut buffer = Vec::with_capacity(capacity);
+        buffer.resize_with(capacity, MaybeUninit::uninit);
+
+        Self::from_storage(buffer)
+    }
+
+    // Construct a CircularBuffer whose capacity is `capacity` rounded up to a power of two.
+    //
+    // Every buffer with such a capacity wraps its indices around with a bitmask, which is
+    // cheaper than the comparison needed otherwise.
+    pub fn new_pow2(capacity: usize) -> Self {
+        Self::new(capacity.next_power_of_two())
+    }
+
+    // Construct a CircularBuffer whose `write` doubles its capacity instead of returning FullBuffer.
+    pub fn with_growth(capacity: usize) -> Self {
+        Self::with_overflow_policy(capacity, OverflowPolicy::Grow)
+    }
+
+    // Construct a CircularBuffer whose `push` follows the given policy when the buffer is full.
+    pub fn with_overflow_policy(capacity: usize, policy: OverflowPolicy) -> Self {
+        let mut buffer = Self::new(capacity);
+        buffer.overflow_policy = policy;
+        buffer
+    }
+
+    // Move the elements from logical index `at` onward into a new buffer, keeping their order.
+    //
+    // The new buffer has the same capacity and overflow policy, and fresh stats.
+    // Panics if `at` is larger than `len()`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(
+            at <= self.len,
+            "split index {} of {} elements",
+            at,
+            self.len
+        );
+
+        let mut other = Self::new(self.capacity());
+        other.overflow_policy = self.overflow_policy;
+        while self.len > at {
+            other.push_start(self.pop_end());
+        }
+
+        other
+    }
+
+    // Split the buffer into a writing and a reading half, which can be kept in different places
+    // of the program, or with the `std` feature, sent to different threads.
+    pub fn split(self) -> (channel::Sender<T>, channel::Receiver<T>) {
+        channel::split(self)
+    }
+
+    // Change the capacity of the buffer, keeping the newest elements that still fit.
+    pub fn resize(&mut self, new_capacity: usize) {
+        let skip = self.len().saturating_sub(new_capacity);
+        for _ in 0..skip {
+            self.pop_start();
+        }
+
+        // A Vec can always be reallocated.
+        self.reallocate(new_capacity);
+    }
+
+    // Reduce the capacity to the number of stored elements, releasing the unused memory.
+    pub fn shrink_to_fit(&mut self) {
+        self.resize(self.len);
+    }
+
+    // Save a copy of the current state, to be brought back later with `restore`.
+    pub fn snapshot(&self) -> Snapshot<T>
+    where
+        T: Clone,
+    {
+        Snapshot {
+            buffer: self.clone(),
+        }
+    }
+
+    // Replace the contents, capacity, overflow policy and stats with the ones of the snapshot.
+    //
+    // The hook set by `on_evict` is not part of the snapshot, so the current one is kept.
+    pub fn restore(&mut self, snapshot: Snapshot<T>) {
+        let hook = self.evict_hook.take();
+        *self = snapshot.buffer;
+        self.evict_hook = hook;
+    }
+}
+
+impl<T, S: Storage<T>> CircularBuffer<T, S> {
+    // Construct an empty CircularBuffer that keeps its elements in `storage`, such as an array
+    // or a borrowed slice of uninitialized slots. The capacity is the number of slots.
+    //
+    // Only a Vec can be reallocated, so buffers with other storages never grow.
+    pub fn from_storage(storage: S) -> Self {
+        let pow2 = storage.as_ref().len().is_power_of_two();
+
+        Self {
+            buffer: storage,
+            start_idx: 0,
+            len: 0,
+            pow2,
+            overflow_policy: OverflowPolicy::default(),
+            stats: Stats::default(),
+            evict_hook: None,
+        }
+    }
+
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    // Pass every element evicted by an overwrite to `hook`, for example to log it or to send
+    // it through a channel. `overwrite` then always returns `None`.
+    //
+    // Clones and snapshots of the buffer do not keep the hook, but restoring a snapshot does.
+    pub fn on_evict(&mut self, hook: impl FnMut(T) + Send + Sync + 'static) {
+        self.evict_hook = Some(Box::new(hook));
+    }
+
+    // Reset every counter, starting the high watermark again at the current length.
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats {
+            high_watermark: self.len,
+            ..Stats::default()
+        };
+    }
+
+    pub fn write(&mut self, element: T) -> Result<(), WriteError<T>> {
+        if !self.make_room() {
+            self.stats.failed_writes += 1;
+            return Err(WriteError(element));
+        }
+
+        self.push_end(element);
+        self.record_writes(1);
+
+        Ok(())
+    }
+
+    // Write the element according to the overflow policy.
+    //
+    // With `OverflowPolicy::Overwrite`, this never fails and returns the evicted element, if any.
+    // Otherwise it behaves like `write`.
+    pub fn push(&mut self, element: T) -> Result<Option<T>, WriteError<T>> {
+        match self.overflow_policy {
+            OverflowPolicy::Reject | OverflowPolicy::Grow => self.write(element).map(|()| None),
+            OverflowPolicy::Overwrite => Ok(self.overwrite(element)),
+        }
+    }
+
+    // Store the element before the oldest one, so that it is the next one to be read.
+    pub fn push_front(&mut self, element: T) -> Result<(), WriteError<T>> {
+        if !self.make_room() {
+            self.stats.failed_writes += 1;
+            return Err(WriteError(element));
+        }
+
+        self.push_start(element);
+        self.record_writes(1);
+
+        Ok(())
+    }
+
+    // Clone as many elements of the slice as fit into the buffer and return how many were written.
+    pub fn write_slice(&mut self, elements: &[T]) -> usize
+    where
+        T: Clone,
+    {
+        let needed = self.len() + elements.len();
+        if self.overflow_policy == OverflowPolicy::Grow && needed > self.capacity() {
+            let mut capacity = self.capacity().max(1);
+            while capacity < needed {
+                capacity *= 2;
+            }
+            self.reallocate(capacity);
+        }
+
+        let count = elements.len().min(self.capacity() - self.len());
+        for element in &elements[..count] {
+            self.push_end(element.clone());
+        }
+        self.record_writes(count);
+        self.stats.failed_writes += (elements.len() - count) as u64;
+
+        count
+    }
+
+    // Write elements of the iterator until the buffer is full, and return how many were written.
+    //
+    // No element is taken from the iterator unless it can be stored, so passing `&mut iter`
+    // allows writing the rest of it later. A growing buffer only grows once an element
+    // is there to fill the new slots.
+    pub fn write_iter(&mut self, elements: impl IntoIterator<Item = T>) -> usize {
+        let mut elements = elements.into_iter();
+        let mut count = 0;
+        while self.can_make_room() {
+            let Some(element) = elements.next() else {
+                break;
+            };
+            self.make_room();
+            self.push_end(element);
+            count += 1;
+        }
+        self.record_writes(count);
+
+        count
+    }
+
+    pub fn read(&mut self) -> Result<T, Error> {
+        if self.is_empty() {
+            return Err(Error::EmptyBuffer);
+        }
+
+        self.stats.reads += 1;
+        Ok(self.pop_start())
+    }
+
+    // Move as many of the oldest elements as fit into `out`, and return how many were moved.
+    pub fn read_into(&mut self, out: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let (head, tail) = self.as_slices();
+        let head_len = head.len().min(out.len());
+        let tail_len = tail.len().min(out.len() - head_len);
+        out[..head_len].copy_from_slice(&head[..head_len]);
+        out[head_len..head_len + tail_len].copy_from_slice(&tail[..tail_len]);
+
+        // The elements are `Copy`, so the slots can be released without dropping them.
+        let count = head_len + tail_len;
+        self.start_idx = self.physical_idx(count);
+        self.len -= count;
+        self.stats.reads += count as u64;
+
+        count
+    }
+
+    /// Write the element without checking that there is a free slot, and without following
+    /// the overflow policy.
+    ///
+    /// # Safety
+    ///
+    /// The caller must make sure that the buffer is not full, for example by checking
+    /// `len()` once before writing several elements.
+    pub unsafe fn write_unchecked(&mut self, element: T) {
+        debug_assert!(!self.is_full());
+
+        let idx = self.physical_idx(self.len);
+        // SAFETY: the buffer is not full, so `idx` is the index of a free slot.
+        unsafe { self.buffer.as_mut().get_unchecked_mut(idx).write(element) };
+        self.len += 1;
+        self.record_writes(1);
+    }
+
+    /// Read the oldest element without checking that there is one.
+    ///
+    /// # Safety
+    ///
+    /// The caller must make sure that the buffer is not empty.
+    pub unsafe fn read_unchecked(&mut self) -> T {
+        debug_assert!(!self.is_empty());
+
+        // SAFETY: the buffer is not empty, so the slot at `start_idx` is initialized,
+        // and it is treated as uninitialized from now on.
+        let element = unsafe {
+            self.buffer
+                .as_mut()
+                .get_unchecked(self.start_idx)
+                .assume_init_read()
+        };
+        self.start_idx = self.wrap(self.start_idx + 1);
+        self.len -= 1;
+        self.stats.reads += 1;
+
+        element
+    }
+
+    // Remove the newest element from the buffer.
+    pub fn pop_back(&mut self) -> Result<T, Error> {
+        if self.is_empty() {
+            return Err(Error::EmptyBuffer);
+        }
+
+        self.stats.reads += 1;
+        Ok(self.pop_end())
+    }
+
+    // Read the newest element, the same as `pop_back`. Together with `overwrite`, this makes
+    // the buffer a bounded stack whose oldest elements fall off when it is full.
+    pub fn read_back(&mut self) -> Result<T, Error> {
+        self.pop_back()
+    }
+
+    // Remove up to `n` of the oldest elements, fewer if the buffer runs out of elements.
+    pub fn read_many(&mut self, n: usize) -> Vec<T> {
+        let count = n.min(self.len());
+        (0..count).map(|_| self.read().unwrap()).collect()
+    }
+
+    // Return the oldest element without removing it from the buffer.
+    pub fn peek(&self) -> Result<&T, Error> {
+        self.get(0).ok_or(Error::EmptyBuffer)
+    }
+
+    // Return a mutable reference to the oldest element without removing it from the buffer.
+    pub fn peek_mut(&mut self) -> Result<&mut T, Error> {
+        self.get_mut(0).ok_or(Error::EmptyBuffer)
+    }
+
+    // Return the n-th oldest element, or `None` if there are not that many elements.
+    pub fn get(&self, n: usize) -> Option<&T> {
+        if n >= self.len() {
+            return None;
+        }
+
+        // SAFETY: the first `len` logical slots are initialized.
+        Some(unsafe { self.buffer.as_ref()[self.physical_idx(n)].assume_init_ref() })
+    }
+
+    pub fn get_mut(&mut self, n: usize) -> Option<&mut T> {
+        if n >= self.len() {
+            return None;
+        }
+
+        let idx = self.physical_idx(n);
+        // SAFETY: the first `len` logical slots are initialized.
+        Some(unsafe { self.buffer.as_mut()[idx].assume_init_mut() })
+    }
+
+    // Return the oldest element, or `None` if the buffer is empty.
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    // Return the newest element, or `None` if the buffer is empty.
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(self.len.checked_sub(1)?)
+    }
+
+    // Clone the stored elements into a Vec, from the oldest to the newest.
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|element| element == x)
+    }
+
+    // Return the logical index of the oldest element matching the predicate.
+    pub fn position(&self, pred: impl FnMut(&T) -> bool) -> Option<usize> {
+        self.iter().position(pred)
+    }
+
+    // Iterate over the stored elements from the oldest to the newest.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (head, tail) = self.as_slices();
+        Iter::new(head, tail)
+    }
+
+    // Iterate over mutable references to the stored elements, from the oldest to the newest.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (head, tail) = self.as_mut_slices();
+        IterMut {
+            head: head.iter_mut(),
+            tail: tail.iter_mut(),
+        }
+    }
+
+    // Iterate over the `n` newest elements, or all of them if there are fewer, the newest last.
+    pub fn peek_last_n(&self, n: usize) -> Iter<'_, T> {
+        let skip = self.len.saturating_sub(n);
+        let (head, tail) = self.as_slices();
+        if skip < head.len() {
+            Iter::new(&head[skip..], tail)
+        } else {
+            Iter::new(&[], &tail[skip - head.len()..])
+        }
+    }
+
+    // Iterate over every run of `N` consecutive elements, from the oldest to the newest,
+    // without copying them. There are none if fewer than `N` elements are stored.
+    //
+    // Panics if `N` is 0.
+    pub fn windows<const N: usize>(&self) -> impl ExactSizeIterator<Item = [&T; N]> {
+        assert!(N > 0, "window size must be non-zero");
+
+        let count = (self.len + 1).saturating_sub(N);
+        (0..count).map(move |start| core::array::from_fn(|i| &self[start + i]))
+    }
+
+    // Return the stored elements as two slices, the oldest elements in the first one.
+    //
+    // The second slice is empty unless the elements wrap around the end of the storage.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let head_len = self.len.min(self.capacity() - self.start_idx);
+        let ptr = self.buffer.as_ref().as_ptr() as *const T;
+
+        // SAFETY: `MaybeUninit<T>` has the same layout as `T`, and both ranges
+        // only cover initialized slots.
+        unsafe {
+            (
+                slice::from_raw_parts(ptr.add(self.start_idx), head_len),
+                slice::from_raw_parts(ptr, self.len - head_len),
+            )
+        }
+    }
+
+    // Like `as_slices`, but the elements can be changed through the slices.
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let head_len = self.len.min(self.capacity() - self.start_idx);
+        let ptr = self.buffer.as_mut().as_mut_ptr() as *mut T;
+
+        // SAFETY: `MaybeUninit<T>` has the same layout as `T`, and both ranges only cover
+        // initialized slots and do not overlap.
+        unsafe {
+            (
+                slice::from_raw_parts_mut(ptr.add(self.start_idx), head_len),
+                slice::from_raw_parts_mut(ptr, self.len - head_len),
+            )
+        }
+    }
+
+    // Rotate the storage so that the stored elements form a single slice, and return it.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        if self.start_idx + self.len > self.capacity() {
+            self.buffer.as_mut().rotate_left(self.start_idx);
+            self.start_idx = 0;
+        }
+
+        // SAFETY: the elements no longer wrap around, so the slots from `start_idx`
+        // to `start_idx + len` are initialized.
+        unsafe {
+            let ptr = self.buffer.as_mut().as_mut_ptr() as *mut T;
+            slice::from_raw_parts_mut(ptr.add(self.start_idx), self.len)
+        }
+    }
+
+    // Remove and yield every stored element from the oldest to the newest.
+    //
+    // The buffer is left empty even if the iterator is dropped before it is exhausted.
+    pub fn drain(&mut self) -> Drain<'_, T, S> {
+        Drain { buffer: self }
+    }
+
+    // Remove the element at logical index `idx`, or return `None` if there are not that many
+    // elements. The elements on the shorter side of it are moved over to close the gap.
+    pub fn remove(&mut self, idx: usize) -> Option<T> {
+        if idx >= self.len {
+            return None;
+        }
+
+        // Swap the element towards the nearest end, and pop it from there.
+        if idx < self.len - 1 - idx {
+            for i in (0..idx).rev() {
+                self.swap_slots(i, i + 1);
+            }
+            Some(self.pop_start())
+        } else {
+            for i in idx..self.len - 1 {
+                self.swap_slots(i, i + 1);
+            }
+            Some(self.pop_end())
+        }
+    }
+
+    // Remove the elements for which `f` returns false, keeping the order of the others.
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        for _ in 0..self.len {
+            let element = self.pop_start();
+            if f(&element) {
+                self.push_end(element);
+            }
+        }
+    }
+
+    // Rotate the elements so that the one at logical index `n` becomes the oldest.
+    //
+    // A full buffer only moves its start index; otherwise the shorter side is moved
+    // across, one element at a time. Panics if `n` is larger than `len()`.
+    pub fn rotate_left(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation by {} of {} elements", n, self.len);
+
+        if self.is_full() {
+            self.start_idx = self.physical_idx(n);
+        } else if n <= self.len - n {
+            for _ in 0..n {
+                let element = self.pop_start();
+                self.push_end(element);
+            }
+        } else {
+            for _ in 0..self.len - n {
+                let element = self.pop_end();
+                self.push_start(element);
+            }
+        }
+    }
+
+    // Rotate the elements so that the `n` newest ones become the oldest.
+    //
+    // Panics if `n` is larger than `len()`.
+    pub fn rotate_right(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation by {} of {} elements", n, self.len);
+
+        self.rotate_left(self.len - n);
+    }
+
+    // Drop the oldest elements until at most `n` are left.
+    //
+    // Like `resize`, and unlike `Vec::truncate`, this keeps the newest elements, which are
+    // the ones a circular buffer would also keep when overwriting.
+    pub fn truncate(&mut self, n: usize) {
+        while self.len > n {
+            self.pop_start();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        while !self.is_empty() {
+            self.pop_start();
+        }
+        self.start_idx = 0;
+    }
+
+    // Write the element, evicting the oldest element if the buffer is full.
+    //
+    // The evicted element is returned, unless it was passed to the hook set by `on_evict`.
+    pub fn overwrite(&mut self, element: T) -> Option<T> {
+        let evicted = if self.capacity() == 0 {
+            // Nothing can ever be stored, so the element itself is evicted right away.
+            self.stats.evictions += 1;
+            Some(element)
+        } else {
+            let evicted = if self.is_full() {
+                self.stats.evictions += 1;
+                Some(self.pop_start())
+            } else {
+                None
+            };
+
+            self.push_end(element);
+            self.record_writes(1);
+
+            evicted
+        };
+
+        match (self.evict_hook.as_mut(), evicted) {
+            (Some(hook), Some(element)) => {
+                hook(element);
+                None
+            }
+            (_, evicted) => evicted,
+        }
+    }
+
+    // Return the number of elements currently stored in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // Return the maximum number of elements the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.buffer.as_ref().len()
+    }
+
+    // Translate a logical index, where 0 is the oldest element, into an index of `buffer`.
+    //
+    // The index must not be larger than the capacity.
+    fn physical_idx(&self, index: usize) -> usize {
+        self.wrap(self.start_idx + index)
+    }
+
+    // Bring an index smaller than twice the capacity back into `buffer`.
+    //
+    // Subtracting instead of taking the remainder also works with a capacity of 0.
+    fn wrap(&self, idx: usize) -> usize {
+        if self.pow2 {
+            idx & (self.capacity() - 1)
+        } else if idx >= self.capacity() {
+            idx - self.capacity()
+        } else {
+            idx
+        }
+    }
+
+    // Make sure there is a free slot, growing the buffer if the overflow policy allows it.
+    fn make_room(&mut self) -> bool {
+        if self.is_full() {
+            if self.overflow_policy != OverflowPolicy::Grow {
+                return false;
+            }
+
+            return self.reallocate((self.capacity() * 2).max(1));
+        }
+
+        true
+    }
+
+    // Return whether `make_room` would succeed, without growing the buffer.
+    fn can_make_room(&self) -> bool {
+        !self.is_full() || (self.overflow_policy == OverflowPolicy::Grow && S::CAN_GROW)
+    }
+
+    // Move the elements into a newly allocated storage with `capacity` slots, which must be
+    // enough for all of them. Return false, leaving the buffer unchanged, if the kind of
+    // storage cannot be allocated.
+    fn reallocate(&mut self, capacity: usize) -> bool {
+        let Some(mut buffer) = S::allocate(capacity) else {
+            return false;
+        };
+
+        let len = self.len;
+        for slot in &mut buffer.as_mut()[..len] {
+            slot.write(self.pop_start());
+        }
+
+        self.buffer = buffer;
+        self.start_idx = 0;
+        self.len = len;
+        self.pow2 = capacity.is_power_of_two();
+
+        true
+    }
+
+    // Swap the slots of two logical indices, which must not be larger than the capacity.
+    fn swap_slots(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.physical_idx(a), self.physical_idx(b));
+        self.buffer.as_mut().swap(a, b);
+    }
+
+    // Clone the elements, the overflow policy and the stats into `other`, an empty buffer
+    // with the same capacity. The eviction hook cannot be cloned, so `other` has none.
+    fn clone_elements_into(&self, mut other: Self) -> Self
+    where
+        T: Clone,
+    {
+        debug_assert!(other.is_empty() && other.capacity() == self.capacity());
+
+        other.overflow_policy = self.overflow_policy;
+        other.stats = self.stats;
+        for element in self.iter() {
+            other.push_end(element.clone());
+        }
+
+        other
+    }
+
+    fn record_writes(&mut self, count: usize) {
+        self.stats.writes += count as u64;
+        self.stats.high_watermark = self.stats.high_watermark.max(self.len);
+    }
+
+    // Store the element after the newest one. The buffer must not be full.
+    fn push_end(&mut self, element: T) {
+        debug_assert!(!self.is_full());
+
+        let idx = self.physical_idx(self.len);
+        self.buffer.as_mut()[idx].write(element);
+        self.len += 1;
+    }
+
+    // Move the oldest element out of the buffer. The buffer must not be empty.
+    fn pop_start(&mut self) -> T {
+        assert!(!self.is_empty());
+
+        // SAFETY: the buffer is not empty, so the slot at `start_idx` is initialized,
+        // and it is treated as uninitialized from now on.
+        let element = unsafe { self.buffer.as_mut()[self.start_idx].assume_init_read() };
+        self.start_idx = self.wrap(self.start_idx + 1);
+        self.len -= 1;
+
+        element
+    }
+
+    // Store the element before the oldest one. The buffer must not be full.
+    fn push_start(&mut self, element: T) {
+        debug_assert!(!self.is_full());
+
+        self.start_idx = self.wrap(self.start_idx + self.capacity() - 1);
+        self.buffer.as_mut()[self.start_idx].write(element);
+        self.len += 1;
+    }
+
+    // Move the newest element out of the buffer. The buffer must not be empty.
+    fn pop_end(&mut self) -> T {
+        assert!(!self.is_empty());
+
+        self.len -= 1;
+        let idx = self.physical_idx(self.len);
+
+        // SAFETY: the buffer was not empty, so the slot of the newest element is initialized,
+        // and it is treated as uninitialized from now on.
+        unsafe { self.buffer.as_mut()[idx].assume_init_read() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+}
+
+// A number that `CircularBuffer::mean` can average, converted like with `as f64`.
+//
+// Unlike `Into<f64>`, it is also implemented for the 64-bit and pointer-sized integers,
+// such as frame times in microseconds, which may lose precision past 2^53.
+pub trait AsF64: Copy {
+    fn as_f64(self) -> f64;
+}
+
+macro_rules! impl_as_f64 {
+    ($($t:ty),*) => {
+        $(
+            impl AsF64 for $t {
+                fn as_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_as_f64!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+// Aggregates over the stored elements, for example to use the buffer as a rolling window
+// of frame times.
+//
+// Each call scans the current contents, in O(n). Running totals would have to be updated
+// through every method that changes elements in place, such as `get_mut`, `iter_mut`,
+// `IndexMut` or `retain`, which costs every other user of the buffer for a window that
+// is usually small.
+impl<T: Copy, S: Storage<T>> CircularBuffer<T, S> {
+    pub fn sum(&self) -> T
+    where
+        T: Sum,
+    {
+        self.iter().copied().sum()
+    }
+
+    // Return the average of the elements, or `None` if the buffer is empty.
+    pub fn mean(&self) -> Option<f64>
+    where
+        T: AsF64,
+    {
+        if self.is_empty() {
+            return None;
+        }
+
+        let sum: f64 = self.iter().map(|&element| element.as_f64()).sum();
+        Some(sum / self.len as f64)
+    }
+
+    // Return the smallest element, or `None` if the buffer is empty.
+    //
+    // Elements that cannot be compared, such as NaN, are only returned if they come first.
+    pub fn min(&self) -> Option<T>
+    where
+        T: PartialOrd,
+    {
+        self.iter()
+            .copied()
+            .reduce(|min, element| if element < min { element } else { min })
+    }
+
+    // Return the largest element, or `None` if the buffer is empty.
+    //
+    // Elements that cannot be compared, such as NaN, are only returned if they come first.
+    pub fn max(&self) -> Option<T>
+    where
+        T: PartialOrd,
+    {
+        self.iter()
+            .copied()
+            .reduce(|max, element| if element > max { element } else { max })
+    }
+}
+
+// Insert the elements of the iterator according to the buffer's `OverflowPolicy`.
+impl<T, S: Storage<T>> Extend<T> for CircularBuffer<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            if self.push(element).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+// Build a full buffer whose capacity is the number of collected elements.
+impl<T> FromIterator<T> for CircularBuffer<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let buffer: Vec<MaybeUninit<T>> = iter.into_iter().map(MaybeUninit::new).collect();
+        let len = buffer.len();
+
+        Self {
+            buffer,
+            start_idx: 0,
+            len,
+            pow2: len.is_power_of_two(),
+            overflow_policy: OverflowPolicy::default(),
+            stats: Stats {
+                high_watermark: len,
+                ..Stats::default()
+            },
+            evict_hook: None,
+        }
+    }
+}
+
+// Build a full buffer whose oldest element is the first one of the Vec.
+impl<T> From<Vec<T>> for CircularBuffer<T> {
+    fn from(elements: Vec<T>) -> Self {
+        elements.into_iter().collect()
+    }
+}
+
+// Move the elements into a Vec, from the oldest to the newest.
+impl<T, S: Storage<T>> From<CircularBuffer<T, S>> for Vec<T> {
+    fn from(buffer: CircularBuffer<T, S>) -> Self {
+        buffer.into_iter().collect()
+    }
+}
+
+impl<T, S: Storage<T>> Drop for CircularBuffer<T, S> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Clone> Clone for CircularBuffer<T> {
+    fn clone(&self) -> Self {
+        self.clone_elements_into(Self::new(self.capacity()))
+    }
+}
+
+impl<T: fmt::Debug, S: Storage<T>> fmt::Debug for CircularBuffer<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Show the elements from the oldest to the newest, followed by the occupancy,
+// for example `[1, 2, 3] (3/4)`.
+impl<T: fmt::Display, S: Storage<T>> fmt::Display for CircularBuffer<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, element) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", element)?;
+        }
+        write!(f, "] ({}/{})", self.len, self.capacity())
+    }
+}
+
+// Two buffers are equal if they hold the same elements in the same order,
+// regardless of their capacities or where the elements are stored.
+impl<T: PartialEq, S: Storage<T>, U: Storage<T>> PartialEq<CircularBuffer<T, U>>
+    for CircularBuffer<T, S>
+{
+    fn eq(&self, other: &CircularBuffer<T, U>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, S: Storage<T>> Eq for CircularBuffer<T, S> {}
+
+// Like the equality, the hash only depends on the elements and their order.
+impl<T: Hash, S: Storage<T>> Hash for CircularBuffer<T, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        self.iter().for_each(|element| element.hash(state));
+    }
+}
+
+// Index 0 is the oldest element and `len() - 1` is the newest one.
+impl<T, S: Storage<T>> Index<usize> for CircularBuffer<T, S> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        let len = self.len();
+        self.get(index).unwrap_or_else(|| {
+            panic!(
+                "index out of bounds: the len is {} but the index is {}",
+                len, index
+            )
+        })
+    }
+}
+
+impl<T, S: Storage<T>> IndexMut<usize> for CircularBuffer<T, S> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        let len = self.len();
+        self.get_mut(index).unwrap_or_else(|| {
+            panic!(
+                "index out of bounds: the len is {} but the index is {}",
+                len, index
+            )
+        })
+    }
+}
+
+// A saved state of a CircularBuffer created by `CircularBuffer::snapshot`.
+#[derive(Clone, Debug)]
+pub struct Snapshot<T> {
+    buffer: CircularBuffer<T>,
+}
+
+// An iterator over references to the elements of a circular buffer, from the oldest to the newest.
+pub struct Iter<'a, T> {
+    head: slice::Iter<'a, T>,
+    tail: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    // Iterate over `head` and then over `tail`, as returned by `as_slices`.
+    fn new(head: &'a [T], tail: &'a [T]) -> Self {
+        Self {
+            head: head.iter(),
+            tail: tail.iter(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.head.next().or_else(|| self.tail.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.head.len() + self.tail.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.tail.next_back().or_else(|| self.head.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+// Cloning the iterator keeps its position, like for the iterators of slices.
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            head: self.head.clone(),
+            tail: self.tail.clone(),
+        }
+    }
+}
+
+// An iterator over mutable references to the elements of a CircularBuffer.
+pub struct IterMut<'a, T> {
+    head: slice::IterMut<'a, T>,
+    tail: slice::IterMut<'a, T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.head.next().or_else(|| self.tail.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.head.len() + self.tail.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.tail.next_back().or_else(|| self.head.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+// An iterator that moves the elements out of a CircularBuffer from the oldest to the newest.
+pub struct IntoIter<T, S: Storage<T> = Vec<MaybeUninit<T>>> {
+    buffer: CircularBuffer<T, S>,
+}
+
+impl<T, S: Storage<T>> Iterator for IntoIter<T, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.read().ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.buffer.len(), Some(self.buffer.len()))
+    }
+}
+
+impl<T, S: Storage<T>> DoubleEndedIterator for IntoIter<T, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.buffer.pop_back().ok()
+    }
+}
+
+impl<T, S: Storage<T>> ExactSizeIterator for IntoIter<T, S> {}
+
+impl<T, S: Storage<T>> FusedIterator for IntoIter<T, S> {}
+
+impl<T, S: Storage<T>> IntoIterator for CircularBuffer<T, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { buffer: self }
+    }
+}
+
+// A draining iterator over the elements of a CircularBuffer.
+pub struct Drain<'a, T, S: Storage<T> = Vec<MaybeUninit<T>>> {
+    buffer: &'a mut CircularBuffer<T, S>,
+}
+
+impl<T, S: Storage<T>> Iterator for Drain<'_, T, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.read().ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.buffer.len(), Some(self.buffer.len()))
+    }
+}
+
+impl<T, S: Storage<T>> DoubleEndedIterator for Drain<'_, T, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.buffer.pop_back().ok()
+    }
+}
+
+impl<T, S: Storage<T>> ExactSizeIterator for Drain<'_, T, S> {}
+
+impl<T, S: Storage<T>> FusedIterator for Drain<'_, T, S> {}
+
+impl<T, S: Storage<T>> Drop for Drain<'_, T, S> {
+    fn drop(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_on_read_empty_buffer() {
+        let mut buffer = CircularBuffer::<char>::new(1);
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+    }
+
+    #[test]
+    fn can_read_item_just_written() {
+        let mut buffer = CircularBuffer::new(1);
+        assert!(buffer.write('1').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+    }
+
+    #[test]
+    fn each_item_may_only_be_read_once() {
+        let mut buffer = CircularBuffer::new(1);
+        assert!(buffer.write('1').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+    }
+
+    #[test]
+    fn items_are_read_in_the_order_they_are_written() {
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+    }
+
+    #[test]
+    fn full_buffer_cant_be_written_to() {
+        let mut buffer = CircularBuffer::new(1);
+        assert!(buffer.write('1').is_ok());
+        assert_eq!(Err(WriteError('2')), buffer.write('2'));
+    }
+
+    #[test]
+    fn read_frees_up_capacity_for_another_write() {
+        let mut buffer = CircularBuffer::new(1);
+        assert!(buffer.write('1').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(Ok('2'), buffer.read());
+    }
+
+    #[test]
+    fn read_position_is_maintained_even_across_multiple_writes() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('3').is_ok());
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Ok('3'), buffer.read());
+    }
+
+    #[test]
+    fn items_cleared_out_of_buffer_cant_be_read() {
+        let mut buffer = CircularBuffer::new(1);
+        assert!(buffer.write('1').is_ok());
+        buffer.clear();
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+    }
+
+    #[test]
+    fn clear_frees_up_capacity_for_another_write() {
+        let mut buffer = CircularBuffer::new(1);
+        assert!(buffer.write('1').is_ok());
+        buffer.clear();
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(Ok('2'), buffer.read());
+    }
+
+    #[test]
+    fn clear_does_nothing_on_empty_buffer() {
+        let mut buffer = CircularBuffer::new(1);
+        buffer.clear();
+        assert!(buffer.write('1').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+    }
+
+    #[test]
+    fn clear_actually_frees_up_its_elements() {
+        let mut buffer = CircularBuffer::new(1);
+        let element = Rc::new(());
+        assert!(buffer.write(Rc::clone(&element)).is_ok());
+        assert_eq!(Rc::strong_count(&element), 2);
+        buffer.clear();
+        assert_eq!(Rc::strong_count(&element), 1);
+    }
+
+    #[test]
+    fn overwrite_acts_like_write_on_non_full_buffer() {
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write('1').is_ok());
+        buffer.overwrite('2');
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+    }
+
+    #[test]
+    fn overwrite_replaces_the_oldest_item_on_full_buffer() {
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        buffer.overwrite('A');
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Ok('A'), buffer.read());
+    }
+
+    #[test]
+    fn overwrite_replaces_the_oldest_item_remaining_in_buffer_following_a_read() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert!(buffer.write('3').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('4').is_ok());
+        buffer.overwrite('5');
+        assert_eq!(Ok('3'), buffer.read());
+        assert_eq!(Ok('4'), buffer.read());
+        assert_eq!(Ok('5'), buffer.read());
+    }
+
+    #[test]
+    fn integer_buffer() {
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write(1).is_ok());
+        assert!(buffer.write(2).is_ok());
+        assert_eq!(Ok(1), buffer.read());
+        assert!(buffer.write(-1).is_ok());
+        assert_eq!(Ok(2), buffer.read());
+        assert_eq!(Ok(-1), buffer.read());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+    }
+
+    #[test]
+    fn string_buffer() {
+        let mut buffer = CircularBuffer::new(2);
+        buffer.write("".to_string()).unwrap();
+        buffer.write("Testing".to_string()).unwrap();
+        assert_eq!(0, buffer.read().unwrap().len());
+        assert_eq!(Ok("Testing".to_string()), buffer.read());
+    }
+
+    #[test]
+    fn error_on_peek_empty_buffer() {
+        let buffer = CircularBuffer::<char>::new(1);
+        assert_eq!(Err(Error::EmptyBuffer), buffer.peek());
+    }
+
+    #[test]
+    fn peek_does_not_remove_the_oldest_item() {
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(Ok(&'1'), buffer.peek());
+        assert_eq!(Ok(&'1'), buffer.peek());
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Ok(&'2'), buffer.peek());
+    }
+
+    #[test]
+    fn peek_mut_modifies_the_oldest_item_in_place() {
+        let mut buffer = CircularBuffer::new(2);
+        assert_eq!(Err(Error::EmptyBuffer), buffer.peek_mut());
+        assert!(buffer.write(1).is_ok());
+        assert!(buffer.write(2).is_ok());
+        *buffer.peek_mut().unwrap() += 10;
+        assert_eq!(Ok(11), buffer.read());
+        assert_eq!(Ok(2), buffer.read());
+    }
+
+    #[test]
+    fn len_and_capacity_track_the_stored_items() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(3, buffer.capacity());
+        assert_eq!(0, buffer.len());
+        assert!(buffer.is_empty());
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(2, buffer.len());
+        assert!(buffer.write('3').is_ok());
+        assert_eq!(3, buffer.len());
+        assert!(buffer.is_full());
+        assert_eq!(3, buffer.capacity());
+    }
+
+    #[test]
+    fn len_handles_wrapped_buffer() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert!(buffer.write('3').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Ok('2'), buffer.read());
+        assert!(buffer.write('4').is_ok());
+        assert_eq!(2, buffer.len());
+        assert!(!buffer.is_empty());
+        assert!(!buffer.is_full());
+    }
+
+    #[test]
+    fn iter_does_not_consume_the_buffer() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(vec![&'1', &'2'], buffer.iter().collect::<Vec<_>>());
+        assert_eq!(2, buffer.len());
+    }
+
+    #[test]
+    fn iter_follows_the_wrap_around() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert!(buffer.write('3').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('4').is_ok());
+        assert_eq!(vec![&'2', &'3', &'4'], buffer.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn into_iter_yields_owned_items_in_order() {
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write("1".to_string()).is_ok());
+        assert!(buffer.write("2".to_string()).is_ok());
+        assert_eq!(Ok("1".to_string()), buffer.read());
+        assert!(buffer.write("3".to_string()).is_ok());
+        let items: Vec<String> = buffer.into_iter().collect();
+        assert_eq!(vec!["2".to_string(), "3".to_string()], items);
+    }
+
+    #[test]
+    fn drain_empties_the_buffer() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(vec!['1', '2'], buffer.drain().collect::<Vec<_>>());
+        assert!(buffer.is_empty());
+        assert!(buffer.write('3').is_ok());
+        assert_eq!(Ok('3'), buffer.read());
+    }
+
+    #[test]
+    fn dropping_drain_early_still_frees_up_its_elements() {
+        let mut buffer = CircularBuffer::new(2);
+        let element = Rc::new(());
+        assert!(buffer.write(Rc::clone(&element)).is_ok());
+        assert!(buffer.write(Rc::clone(&element)).is_ok());
+        assert_eq!(Rc::strong_count(&element), 3);
+        assert!(buffer.drain().next().is_some());
+        assert_eq!(Rc::strong_count(&element), 1);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn extend_stops_when_full_by_default() {
+        let mut buffer = CircularBuffer::new(2);
+        assert_eq!(OverflowPolicy::Reject, buffer.overflow_policy());
+        buffer.extend(['1', '2', '3']);
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+    }
+
+    #[test]
+    fn extend_overwrites_the_oldest_items_with_overwrite_policy() {
+        let mut buffer = CircularBuffer::new(2);
+        buffer.set_overflow_policy(OverflowPolicy::Overwrite);
+        buffer.extend(['1', '2', '3']);
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Ok('3'), buffer.read());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+    }
+
+    #[test]
+    fn collect_builds_a_full_buffer() {
+        let mut buffer: CircularBuffer<char> = "123".chars().collect();
+        assert_eq!(3, buffer.capacity());
+        assert!(buffer.is_full());
+        assert_eq!(Err(WriteError('4')), buffer.write('4'));
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Ok('3'), buffer.read());
+    }
+
+    #[test]
+    fn index_starts_at_the_oldest_item() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert!(buffer.write('3').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('4').is_ok());
+        assert_eq!('2', buffer[0]);
+        assert_eq!('3', buffer[1]);
+        assert_eq!('4', buffer[2]);
+        buffer[2] = 'A';
+        assert_eq!('A', buffer[2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn index_past_the_newest_item_panics() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        let _ = buffer[1];
+    }
+
+    #[test]
+    fn get_returns_none_past_the_newest_item() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(Some(&'1'), buffer.get(0));
+        assert_eq!(Some(&'2'), buffer.get(1));
+        assert_eq!(None, buffer.get(2));
+        assert_eq!(None, buffer.get_mut(2));
+        *buffer.get_mut(1).unwrap() = 'B';
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Ok('B'), buffer.read());
+    }
+
+    #[test]
+    fn clone_is_independent_of_the_original() {
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write('1').is_ok());
+        let mut cloned = buffer.clone();
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Ok('1'), cloned.read());
+        assert_eq!(Err(Error::EmptyBuffer), cloned.read());
+    }
+
+    #[test]
+    fn debug_prints_the_items_in_order() {
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('3').is_ok());
+        assert_eq!("['2', '3']", format!("{:?}", buffer));
+    }
+
+    #[test]
+    fn equality_ignores_the_start_offset() {
+        let mut first = CircularBuffer::new(2);
+        assert!(first.write('1').is_ok());
+        assert!(first.write('2').is_ok());
+        assert_eq!(Ok('1'), first.read());
+        assert!(first.write('3').is_ok());
+
+        let mut second = CircularBuffer::new(2);
+        assert!(second.write('2').is_ok());
+        assert!(second.write('3').is_ok());
+
+        assert_eq!(first, second);
+        assert_eq!(Ok('2'), second.read());
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn growing_keeps_the_items_in_order() {
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('3').is_ok());
+        buffer.resize(4);
+        assert_eq!(4, buffer.capacity());
+        assert!(buffer.write('4').is_ok());
+        assert!(buffer.write('5').is_ok());
+        assert_eq!(Err(WriteError('6')), buffer.write('6'));
+        assert_eq!(vec!['2', '3', '4', '5'], buffer.drain().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn shrinking_drops_the_oldest_items() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert!(buffer.write('3').is_ok());
+        buffer.resize(2);
+        assert!(buffer.is_full());
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Ok('3'), buffer.read());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+    }
+
+    #[test]
+    fn growing_buffer_doubles_instead_of_rejecting_writes() {
+        let mut buffer = CircularBuffer::with_growth(2);
+        assert!(buffer.write('1').is_ok());
+        assert!(buffer.write('2').is_ok());
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('3').is_ok());
+        assert!(buffer.write('4').is_ok());
+        assert_eq!(4, buffer.capacity());
+        buffer.extend(['5', '6', '7']);
+        assert_eq!(8, buffer.capacity());
+        assert_eq!(
+            vec!['2', '3', '4', '5', '6', '7'],
+            buffer.drain().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn write_slice_stops_at_capacity() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write(1).is_ok());
+        assert_eq!(2, buffer.write_slice(&[2, 3, 4]));
+        assert_eq!(0, buffer.write_slice(&[5]));
+        assert_eq!(Ok(1), buffer.read());
+        assert_eq!(1, buffer.write_slice(&[5, 6]));
+        assert_eq!(vec![2, 3, 5], buffer.drain().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn write_slice_grows_a_growing_buffer() {
+        let mut buffer = CircularBuffer::with_growth(2);
+        assert_eq!(5, buffer.write_slice(&[1, 2, 3, 4, 5]));
+        assert_eq!(8, buffer.capacity());
+        assert_eq!(vec![1, 2, 3, 4, 5], buffer.drain().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn read_many_returns_at_most_the_stored_items() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(3, buffer.write_slice(&['1', '2', '3']));
+        assert_eq!(vec!['1', '2'], buffer.read_many(2));
+        assert_eq!(vec!['3'], buffer.read_many(5));
+        assert!(buffer.read_many(1).is_empty());
+    }
+
+    #[test]
+    fn as_slices_splits_at_the_wrap_around() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(3, buffer.write_slice(&[1, 2, 3]));
+        let empty: &[i32] = &[];
+        assert_eq!((&[1, 2, 3][..], empty), buffer.as_slices());
+        assert_eq!(Ok(1), buffer.read());
+        assert_eq!(Ok(2), buffer.read());
+        assert!(buffer.write(4).is_ok());
+        assert_eq!((&[3][..], &[4][..]), buffer.as_slices());
+    }
+
+    #[test]
+    fn make_contiguous_keeps_the_items_in_order() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        assert_eq!(Ok(1), buffer.read());
+        assert!(buffer.write(5).is_ok());
+        assert_eq!(&mut [2, 3, 4, 5], buffer.make_contiguous());
+        assert_eq!((&[2, 3, 4, 5][..], &[][..]), buffer.as_slices());
+        assert_eq!(Ok(2), buffer.read());
+        assert!(buffer.write(6).is_ok());
+        assert_eq!(vec![3, 4, 5, 6], buffer.drain().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn dropping_the_buffer_frees_up_its_elements() {
+        let element = Rc::new(());
+        let mut buffer = CircularBuffer::new(2);
+        assert!(buffer.write(Rc::clone(&element)).is_ok());
+        assert!(buffer.write(Rc::clone(&element)).is_ok());
+        assert!(buffer.read().is_ok());
+        assert!(buffer.write(Rc::clone(&element)).is_ok());
+        assert_eq!(Rc::strong_count(&element), 3);
+        drop(buffer);
+        assert_eq!(Rc::strong_count(&element), 1);
+    }
+
+    #[test]
+    fn push_front_is_read_first() {
+        let mut buffer = CircularBuffer::new(3);
+        assert!(buffer.write('2').is_ok());
+        assert!(buffer.push_front('1').is_ok());
+        assert!(buffer.write('3').is_ok());
+        assert_eq!(Err(WriteError('0')), buffer.push_front('0'));
+        assert_eq!(Ok('1'), buffer.read());
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Ok('3'), buffer.read());
+    }
+
+    #[test]
+    fn pop_back_removes_the_newest_item() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(Err(Error::EmptyBuffer), buffer.pop_back());
+        assert_eq!(3, buffer.write_slice(&['1', '2', '3']));
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('4').is_ok());
+        assert_eq!(Ok('4'), buffer.pop_back());
+        assert_eq!(Ok('3'), buffer.pop_back());
+        assert!(buffer.write('5').is_ok());
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Ok('5'), buffer.read());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.pop_back());
+    }
+
+    #[test]
+    fn retain_keeps_the_matching_items_in_order() {
+        let mut buffer = CircularBuffer::new(5);
+        assert_eq!(5, buffer.write_slice(&[1, 2, 3, 4, 5]));
+        assert_eq!(Ok(1), buffer.read());
+        assert!(buffer.write(6).is_ok());
+        buffer.retain(|&x| x % 2 == 0);
+        assert_eq!(3, buffer.len());
+        assert_eq!(2, buffer.write_slice(&[7, 8, 9]));
+        assert_eq!(vec![2, 4, 6, 7, 8], buffer.drain().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn contains_and_position_search_the_stored_items() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(3, buffer.write_slice(&[1, 2, 3]));
+        assert_eq!(Ok(1), buffer.read());
+        assert!(buffer.write(4).is_ok());
+        assert!(buffer.contains(&4));
+        assert!(!buffer.contains(&1));
+        assert_eq!(Some(0), buffer.position(|&x| x > 1));
+        assert_eq!(Some(2), buffer.position(|&x| x == 4));
+        assert_eq!(None, buffer.position(|&x| x > 4));
+    }
+
+    #[test]
+    fn overwrite_returns_the_evicted_item() {
+        let mut buffer = CircularBuffer::new(2);
+        assert_eq!(None, buffer.overwrite('1'));
+        assert_eq!(None, buffer.overwrite('2'));
+        assert_eq!(Some('1'), buffer.overwrite('3'));
+        assert_eq!(Some('2'), buffer.overwrite('4'));
+        assert_eq!(Ok('3'), buffer.read());
+        assert_eq!(None, buffer.overwrite('5'));
+    }
+
+    #[test]
+    fn failed_write_gives_the_item_back() {
+        let mut buffer = CircularBuffer::new(1);
+        assert!(buffer.write(String::from("1")).is_ok());
+        let rejected = buffer.write(String::from("2")).unwrap_err();
+        assert_eq!("2", rejected.into_inner());
+        assert_eq!(Error::FullBuffer, Error::from(WriteError(())));
+    }
+
+    #[test]
+    fn errors_can_be_boxed() {
+        fn read_twice(
+            buffer: &mut CircularBuffer<char>,
+        ) -> Result<char, Box<dyn std::error::Error>> {
+            buffer.write('1')?;
+            buffer.read()?;
+            Ok(buffer.read()?)
+        }
+
+        let mut buffer = CircularBuffer::new(1);
+        let error = read_twice(&mut buffer).unwrap_err();
+        assert_eq!(
+            "cannot read from an empty circular buffer",
+            error.to_string()
+        );
+        assert_eq!(
+            "cannot write to a full circular buffer",
+            WriteError('1').to_string()
+        );
+    }
+
+    #[test]
+    fn every_removal_path_frees_up_its_elements() {
+        let element = Rc::new(());
+        let mut buffer = CircularBuffer::new(3);
+        for _ in 0..3 {
+            assert!(buffer.write(Rc::clone(&element)).is_ok());
+        }
+        drop(buffer.overwrite(Rc::clone(&element)));
+        assert!(buffer.pop_back().is_ok());
+        assert_eq!(Rc::strong_count(&element), 3);
+        buffer.retain(|_| false);
+        assert_eq!(Rc::strong_count(&element), 1);
+
+        for _ in 0..3 {
+            assert!(buffer.push_front(Rc::clone(&element)).is_ok());
+        }
+        buffer.resize(1);
+        assert_eq!(Rc::strong_count(&element), 2);
+        drop(buffer.read_many(1));
+        assert_eq!(Rc::strong_count(&element), 1);
+    }
+
+    #[test]
+    fn storage_does_not_wrap_elements_in_option() {
+        let buffer = CircularBuffer::<u32>::new(4);
+        assert_eq!(
+            std::mem::size_of::<u32>() * 4,
+            std::mem::size_of_val(buffer.buffer.as_slice())
+        );
+    }
+
+    #[test]
+    fn restore_rolls_back_to_the_snapshot() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(3, buffer.write_slice(&[1, 2, 3]));
+        assert_eq!(Ok(1), buffer.read());
+        let snapshot = buffer.snapshot();
+
+        assert!(buffer.write(4).is_ok());
+        assert_eq!(Some(2), buffer.overwrite(5));
+        buffer.resize(5);
+        buffer.restore(snapshot.clone());
+        assert_eq!(3, buffer.capacity());
+        assert_eq!(vec![2, 3], buffer.drain().collect::<Vec<_>>());
+
+        buffer.restore(snapshot);
+        assert_eq!(vec![&2, &3], buffer.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stats_count_what_happened_to_the_buffer() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(Stats::default(), buffer.stats());
+        assert_eq!(2, buffer.write_slice(&['1', '2']));
+        assert!(buffer.write('3').is_ok());
+        assert!(buffer.write('4').is_err());
+        assert_eq!(Some('1'), buffer.overwrite('4'));
+        assert_eq!(Ok('2'), buffer.read());
+        assert_eq!(Ok('4'), buffer.pop_back());
+        buffer.resize(5);
+        assert_eq!(
+            Stats {
+                writes: 4,
+                reads: 2,
+                evictions: 1,
+                failed_writes: 1,
+                high_watermark: 3,
+            },
+            buffer.stats()
+        );
+
+        buffer.reset_stats();
+        assert_eq!(
+            Stats {
+                high_watermark: 1,
+                ..Stats::default()
+            },
+            buffer.stats()
+        );
+    }
+
+    #[test]
+    fn evicted_items_are_passed_to_the_hook() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut buffer = CircularBuffer::new(2);
+        buffer.on_evict(move |element| sender.send(element).unwrap());
+        buffer.set_overflow_policy(OverflowPolicy::Overwrite);
+        buffer.extend(['1', '2', '3']);
+        assert_eq!(None, buffer.overwrite('4'));
+        assert_eq!(vec!['1', '2'], receiver.try_iter().collect::<Vec<_>>());
+        assert_eq!(vec!['3', '4'], buffer.drain().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn restoring_a_snapshot_keeps_the_hook() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut buffer = CircularBuffer::with_overflow_policy(1, OverflowPolicy::Overwrite);
+        let snapshot = buffer.snapshot();
+        buffer.on_evict(move |element| sender.send(element).unwrap());
+        buffer.restore(snapshot);
+        assert_eq!(Ok(None), buffer.push('1'));
+        assert_eq!(Ok(None), buffer.push('2'));
+        assert_eq!(vec!['1'], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn buffer_can_be_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CircularBuffer<String>>();
+    }
+
+    #[test]
+    fn zero_capacity_buffer_never_stores_anything() {
+        let mut buffer = CircularBuffer::new(0);
+        assert!(buffer.is_empty());
+        assert!(buffer.is_full());
+        assert_eq!(0, buffer.len());
+        assert_eq!(Err(WriteError('1')), buffer.write('1'));
+        assert_eq!(Err(WriteError('1')), buffer.push_front('1'));
+        assert_eq!(0, buffer.write_slice(&['1']));
+        assert_eq!(Some('1'), buffer.overwrite('1'));
+        assert_eq!(Err(Error::EmptyBuffer), buffer.read());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.pop_back());
+        assert_eq!(Err(Error::EmptyBuffer), buffer.peek());
+        assert_eq!(None, buffer.get(0));
+        assert_eq!(0, buffer.iter().count());
+        assert!(buffer.make_contiguous().is_empty());
+        buffer.clear();
+    }
+
+    #[test]
+    fn zero_capacity_growing_buffer_grows_on_write() {
+        let mut buffer = CircularBuffer::with_growth(0);
+        assert!(buffer.write('1').is_ok());
+        assert_eq!(1, buffer.capacity());
+        assert_eq!(Ok('1'), buffer.read());
+    }
+
+    #[test]
+    fn truncate_keeps_the_newest_items() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        buffer.truncate(5);
+        assert_eq!(4, buffer.len());
+        buffer.truncate(2);
+        assert_eq!(4, buffer.capacity());
+        assert_eq!(vec![&3, &4], buffer.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn shrink_to_fit_reduces_the_capacity_to_the_stored_items() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        assert_eq!(vec![1, 2], buffer.read_many(2));
+        assert!(buffer.write(5).is_ok());
+        buffer.shrink_to_fit();
+        assert_eq!(3, buffer.capacity());
+        assert!(buffer.is_full());
+        assert_eq!(vec![3, 4, 5], buffer.drain().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rotate_full_buffer() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        buffer.rotate_left(1);
+        assert_eq!(vec![&2, &3, &4, &1], buffer.iter().collect::<Vec<_>>());
+        buffer.rotate_right(3);
+        assert_eq!(vec![&3, &4, &1, &2], buffer.iter().collect::<Vec<_>>());
+        buffer.rotate_left(4);
+        assert_eq!(vec![&3, &4, &1, &2], buffer.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rotate_partially_filled_buffer() {
+        let mut buffer = CircularBuffer::new(5);
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        buffer.rotate_left(1);
+        assert_eq!(vec![&2, &3, &4, &1], buffer.iter().collect::<Vec<_>>());
+        buffer.rotate_left(3);
+        assert_eq!(vec![&1, &2, &3, &4], buffer.iter().collect::<Vec<_>>());
+        buffer.rotate_right(1);
+        assert_eq!(vec![&4, &1, &2, &3], buffer.iter().collect::<Vec<_>>());
+        assert!(buffer.write(5).is_ok());
+        assert_eq!(vec![4, 1, 2, 3, 5], buffer.drain().collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "rotation by 3 of 2 elements")]
+    fn rotating_by_more_than_the_length_panics() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(2, buffer.write_slice(&[1, 2]));
+        buffer.rotate_left(3);
+    }
+
+    #[test]
+    fn split_off_moves_the_newest_elements() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        assert_eq!(Ok(1), buffer.read());
+        assert!(buffer.write(5).is_ok());
+
+        let mut other = buffer.split_off(1);
+        assert_eq!(vec![&2], buffer.iter().collect::<Vec<_>>());
+        assert_eq!(vec![&3, &4, &5], other.iter().collect::<Vec<_>>());
+        assert_eq!(4, other.capacity());
+        assert!(other.write(6).is_ok());
+        assert!(other.is_full());
+        assert!(buffer.split_off(1).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "split index 3 of 2 elements")]
+    fn split_off_past_the_length_panics() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(2, buffer.write_slice(&[1, 2]));
+        buffer.split_off(3);
+    }
+
+    #[test]
+    fn buffer_can_be_backed_by_an_array() {
+        let mut buffer = CircularBuffer::from_storage([const { MaybeUninit::uninit() }; 2]);
+        assert!(buffer.write(Rc::new(1)).is_ok());
+        assert!(buffer.write(Rc::new(2)).is_ok());
+        assert!(buffer.is_full());
+        assert_eq!(Some(Rc::new(1)), buffer.overwrite(Rc::new(3)));
+        assert_eq!(CircularBuffer::from_iter([Rc::new(2), Rc::new(3)]), buffer);
+    }
+
+    #[test]
+    fn buffer_can_be_backed_by_a_borrowed_slice() {
+        let mut slots = [const { MaybeUninit::uninit() }; 3];
+        let mut buffer = CircularBuffer::from_storage(&mut slots[..]);
+        assert_eq!(3, buffer.capacity());
+        assert_eq!(3, buffer.write_slice(&['1', '2', '3']));
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.write('4').is_ok());
+        assert_eq!(vec!['2', '3', '4'], buffer.drain().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn fixed_storage_does_not_grow() {
+        let mut buffer = CircularBuffer::from_storage([const { MaybeUninit::uninit() }; 1]);
+        buffer.set_overflow_policy(OverflowPolicy::Grow);
+        assert!(buffer.write('1').is_ok());
+        assert_eq!(Err(WriteError('2')), buffer.write('2'));
+        assert_eq!(0, buffer.write_slice(&['2', '3']));
+        let mut elements = ['2', '3'].into_iter();
+        assert_eq!(0, buffer.write_iter(&mut elements));
+        assert_eq!(Some('2'), elements.next());
+        assert_eq!(1, buffer.capacity());
+    }
+
+    #[test]
+    fn iterators_are_double_ended_and_exact_sized() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        assert_eq!(Ok(1), buffer.read());
+        assert!(buffer.write(5).is_ok());
+
+        let mut iter = buffer.iter();
+        assert_eq!(4, iter.len());
+        assert_eq!(Some(&5), iter.next_back());
+        assert_eq!(Some(&2), iter.next());
+        assert_eq!(2, iter.len());
+        assert_eq!(vec![&4, &3], iter.rev().collect::<Vec<_>>());
+
+        let mut drain = buffer.drain();
+        assert_eq!(Some(5), drain.next_back());
+        assert_eq!(3, drain.len());
+        drop(drain);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn into_iter_can_yield_the_newest_first() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(3, buffer.write_slice(&['1', '2', '3']));
+        let mut iter = buffer.into_iter();
+        assert_eq!(3, iter.len());
+        assert_eq!(Some('1'), iter.next());
+        assert_eq!(vec!['3', '2'], iter.rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn front_and_back_are_the_oldest_and_newest_elements() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(None, buffer.front());
+        assert_eq!(None, buffer.back_mut());
+        assert_eq!(3, buffer.write_slice(&[1, 2, 3]));
+        assert_eq!(Ok(1), buffer.read());
+        assert!(buffer.write(4).is_ok());
+        assert_eq!(Some(&2), buffer.front());
+        assert_eq!(Some(&4), buffer.back());
+
+        *buffer.front_mut().unwrap() = 20;
+        *buffer.back_mut().unwrap() = 40;
+        assert_eq!(vec![&20, &3, &40], buffer.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn conversions_to_and_from_vec_keep_the_order() {
+        let mut buffer = CircularBuffer::from(vec![1, 2, 3]);
+        assert!(buffer.is_full());
+        assert_eq!(Some(1), buffer.overwrite(4));
+        assert_eq!(vec![2, 3, 4], buffer.to_vec());
+        assert_eq!(vec![2, 3, 4], Vec::from(buffer));
+    }
+
+    #[test]
+    fn write_iter_leaves_the_rest_of_the_iterator() {
+        let mut buffer = CircularBuffer::new(3);
+        let mut elements = 1..=5;
+        assert_eq!(3, buffer.write_iter(&mut elements));
+        assert_eq!(Some(4), elements.next());
+        assert_eq!(Ok(1), buffer.read());
+        assert_eq!(1, buffer.write_iter(&mut elements));
+        assert_eq!(0, buffer.write_iter(&mut elements));
+        assert_eq!(vec![2, 3, 5], buffer.to_vec());
+        assert_eq!(4, buffer.stats().writes);
+    }
+
+    #[test]
+    fn write_iter_only_grows_for_elements_it_writes() {
+        let mut buffer = CircularBuffer::with_growth(2);
+        assert_eq!(2, buffer.write_iter([1, 2]));
+        assert_eq!(2, buffer.capacity());
+        assert_eq!(0, buffer.write_iter(core::iter::empty()));
+        assert_eq!(2, buffer.capacity());
+        assert_eq!(1, buffer.write_iter([3]));
+        assert_eq!(4, buffer.capacity());
+        assert_eq!(vec![1, 2, 3], buffer.to_vec());
+    }
+
+    #[test]
+    fn read_into_moves_the_oldest_elements_across_the_wrap_around() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        let mut out = [0; 3];
+        assert_eq!(3, buffer.read_into(&mut out));
+        assert_eq!([1, 2, 3], out);
+        assert_eq!(2, buffer.write_slice(&[5, 6]));
+
+        let mut out = [0; 5];
+        assert_eq!(3, buffer.read_into(&mut out));
+        assert_eq!([4, 5, 6, 0, 0], out);
+        assert!(buffer.is_empty());
+        assert_eq!(0, buffer.read_into(&mut out));
+        assert_eq!(6, buffer.stats().reads);
+    }
+
+    #[test]
+    fn equal_buffers_have_equal_hashes() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash_of(buffer: &CircularBuffer<i32>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            buffer.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut wrapped = CircularBuffer::new(3);
+        assert_eq!(3, wrapped.write_slice(&[1, 2, 3]));
+        wrapped.overwrite(4);
+        let mut larger = CircularBuffer::new(5);
+        assert_eq!(3, larger.write_slice(&[2, 3, 4]));
+        assert_eq!(wrapped, larger);
+        assert_eq!(hash_of(&wrapped), hash_of(&larger));
+
+        larger.pop_back().unwrap();
+        assert_ne!(hash_of(&wrapped), hash_of(&larger));
+    }
+
+    #[test]
+    fn new_pow2_rounds_the_capacity_up() {
+        let mut buffer = CircularBuffer::new_pow2(3);
+        assert_eq!(4, buffer.capacity());
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        assert_eq!(Some(1), buffer.overwrite(5));
+        assert_eq!(Ok(2), buffer.read());
+        assert!(buffer.push_front(2).is_ok());
+        assert_eq!(vec![2, 3, 4, 5], buffer.to_vec());
+
+        buffer.resize(3);
+        assert_eq!(Some(3), buffer.overwrite(6));
+        assert_eq!(vec![4, 5, 6], buffer.to_vec());
+        assert_eq!(1, CircularBuffer::<u8>::new_pow2(0).capacity());
+    }
+
+    #[test]
+    fn push_follows_the_overflow_policy() {
+        let mut buffer = CircularBuffer::with_overflow_policy(1, OverflowPolicy::Reject);
+        assert_eq!(Ok(None), buffer.push('1'));
+        assert_eq!(Err(WriteError('2')), buffer.push('2'));
+
+        let mut buffer = CircularBuffer::with_overflow_policy(1, OverflowPolicy::Overwrite);
+        assert_eq!(Ok(None), buffer.push('1'));
+        assert_eq!(Ok(Some('1')), buffer.push('2'));
+
+        let mut buffer = CircularBuffer::with_overflow_policy(1, OverflowPolicy::Grow);
+        assert_eq!(Ok(None), buffer.push('1'));
+        assert_eq!(Ok(None), buffer.push('2'));
+        assert_eq!(vec!['1', '2'], buffer.to_vec());
+    }
+
+    #[test]
+    fn aggregates_cover_the_current_window() {
+        let mut buffer = CircularBuffer::new(3);
+        assert_eq!(0, buffer.sum());
+        assert_eq!(None, buffer.mean());
+        assert_eq!(None, buffer.min());
+
+        for damage in [4, 9, 2, 7] {
+            buffer.overwrite(damage);
+        }
+        assert_eq!(18, buffer.sum());
+        assert_eq!(Some(6.0), buffer.mean());
+        assert_eq!(Some(2), buffer.min());
+        assert_eq!(Some(9), buffer.max());
+    }
+
+    #[test]
+    fn aggregates_work_with_floats() {
+        let buffer = CircularBuffer::from(vec![16.5f32, 17.0, 15.5]);
+        assert_eq!(49.0, buffer.sum());
+        assert_eq!(Some(49.0 / 3.0), buffer.mean());
+        assert_eq!(Some(15.5), buffer.min());
+        assert_eq!(Some(17.0), buffer.max());
+    }
+
+    #[test]
+    fn mean_works_with_64_bit_integers() {
+        let buffer = CircularBuffer::from(vec![16_667u64, 16_666, 33_334]);
+        assert_eq!(66_667, buffer.sum());
+        assert_eq!(Some(66_667.0 / 3.0), buffer.mean());
+        assert_eq!(Some(1.5), CircularBuffer::from(vec![1usize, 2]).mean());
+    }
+
+    #[test]
+    fn display_shows_the_elements_and_the_occupancy() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!("[] (0/4)", buffer.to_string());
+        assert_eq!(4, buffer.write_slice(&["a", "b", "c", "d"]));
+        assert_eq!(Some("a"), buffer.overwrite("e"));
+        assert_eq!(Ok("b"), buffer.read());
+        assert_eq!("[c, d, e] (3/4)", buffer.to_string());
+    }
+
+    #[test]
+    fn unchecked_reads_and_writes_skip_the_checks() {
+        let mut buffer = CircularBuffer::new(3);
+        let free = buffer.capacity() - buffer.len();
+        for i in 0..free {
+            // SAFETY: only as many elements as there are free slots are written.
+            unsafe { buffer.write_unchecked(i) };
+        }
+        assert!(buffer.is_full());
+        assert_eq!(Ok(0), buffer.read());
+        // SAFETY: one slot was freed by the read.
+        unsafe { buffer.write_unchecked(3) };
+
+        let mut read = Vec::new();
+        while !buffer.is_empty() {
+            // SAFETY: the buffer was just checked not to be empty.
+            read.push(unsafe { buffer.read_unchecked() });
+        }
+        assert_eq!(vec![1, 2, 3], read);
+        assert_eq!(4, buffer.stats().writes);
+        assert_eq!(4, buffer.stats().reads);
+    }
+
+    #[test]
+    fn windows_overlap_across_the_wrap_around() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(0, buffer.windows::<2>().len());
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        buffer.overwrite(5);
+
+        let smoothed: Vec<i32> = buffer.windows().map(|[a, b, c]| a + b + c).collect();
+        assert_eq!(vec![9, 12], smoothed);
+        assert_eq!(
+            vec![[&2], [&3], [&4], [&5]],
+            buffer.windows::<1>().collect::<Vec<_>>()
+        );
+        assert_eq!(0, buffer.windows::<5>().len());
+    }
+
+    #[test]
+    fn remove_closes_the_gap_on_either_side() {
+        let mut buffer = CircularBuffer::new(5);
+        assert_eq!(5, buffer.write_slice(&[0, 1, 2, 3, 4]));
+        assert_eq!(Ok(0), buffer.read());
+        assert!(buffer.write(5).is_ok());
+
+        assert_eq!(Some(2), buffer.remove(1));
+        assert_eq!(vec![1, 3, 4, 5], buffer.to_vec());
+        assert_eq!(Some(4), buffer.remove(2));
+        assert_eq!(vec![1, 3, 5], buffer.to_vec());
+        assert_eq!(None, buffer.remove(3));
+        assert_eq!(2, buffer.write_slice(&[6, 7]));
+        assert!(buffer.is_full());
+        assert_eq!(vec![1, 3, 5, 6, 7], buffer.to_vec());
+    }
+
+    #[test]
+    fn removed_elements_are_not_dropped_twice() {
+        let element = Rc::new(());
+        let mut buffer = CircularBuffer::new(3);
+        for _ in 0..3 {
+            assert!(buffer.write(Rc::clone(&element)).is_ok());
+        }
+        drop(buffer.remove(1));
+        drop(buffer.remove(0));
+        assert_eq!(2, Rc::strong_count(&element));
+        drop(buffer);
+        assert_eq!(1, Rc::strong_count(&element));
+    }
+
+    #[test]
+    fn peek_last_n_views_the_newest_elements() {
+        let mut buffer = CircularBuffer::new(4);
+        assert_eq!(0, buffer.peek_last_n(2).len());
+        assert_eq!(4, buffer.write_slice(&[1, 2, 3, 4]));
+        buffer.overwrite(5);
+        buffer.overwrite(6);
+
+        assert_eq!(vec![&5, &6], buffer.peek_last_n(2).collect::<Vec<_>>());
+        assert_eq!(vec![&4, &5, &6], buffer.peek_last_n(3).collect::<Vec<_>>());
+        assert_eq!(
+            vec![&3, &4, &5, &6],
+            buffer.peek_last_n(10).collect::<Vec<_>>()
+        );
+        assert_eq!(0, buffer.peek_last_n(0).len());
+        assert_eq!(4, buffer.len());
+    }
+
+    #[test]
+    fn read_back_undoes_the_newest_actions_first() {
+        let mut undo = CircularBuffer::new(3);
+        for action in ["move", "attack", "pick up", "drop"] {
+            undo.overwrite(action);
+        }
+        assert_eq!(Ok("drop"), undo.read_back());
+        assert_eq!(Ok("pick up"), undo.read_back());
+        assert_eq!(Ok("attack"), undo.read_back());
+        assert_eq!(Err(Error::EmptyBuffer), undo.read_back());
+    }
+
+    #[test]
+    fn iter_mut_changes_the_elements_in_place() {
+        let mut timers = CircularBuffer::new(3);
+        assert_eq!(3, timers.write_slice(&[1, 2, 3]));
+        assert_eq!(Ok(1), timers.read());
+        assert!(timers.write(4).is_ok());
+
+        timers.iter_mut().for_each(|timer| *timer -= 1);
+        assert_eq!(vec![1, 2, 3], timers.to_vec());
+        *timers.iter_mut().next_back().unwrap() = 10;
+        assert_eq!(3, timers.iter_mut().len());
+        assert_eq!((&mut [1, 2][..], &mut [10][..]), timers.as_mut_slices());
+    }
+}
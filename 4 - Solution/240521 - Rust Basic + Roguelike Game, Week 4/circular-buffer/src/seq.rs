@@ -0,0 +1,124 @@
+use crate::{CircularBuffer, Error, WriteError};
+
+// A CircularBuffer that tags every written element with a sequence number, counting up from 0.
+//
+// A consumer that remembers the last sequence number it saw can tell how many elements
+// were overwritten before it polled again.
+pub struct SeqBuffer<T> {
+    buffer: CircularBuffer<(u64, T)>,
+    // The sequence number of the next element to be written.
+    next_seq: u64,
+}
+
+impl<T> SeqBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: CircularBuffer::new(capacity),
+            next_seq: 0,
+        }
+    }
+
+    // Write the element and return its sequence number.
+    pub fn write(&mut self, element: T) -> Result<u64, WriteError<T>> {
+        let seq = self.next_seq;
+        self.buffer
+            .write((seq, element))
+            .map_err(|WriteError((_, element))| WriteError(element))?;
+        self.next_seq += 1;
+
+        Ok(seq)
+    }
+
+    // Write the element, evicting the oldest element if the buffer is full,
+    // and return the sequence number of the new element with the evicted one.
+    pub fn overwrite(&mut self, element: T) -> (u64, Option<(u64, T)>) {
+        let seq = self.next_seq;
+        let evicted = self.buffer.overwrite((seq, element));
+        self.next_seq += 1;
+
+        (seq, evicted)
+    }
+
+    // Read the oldest element together with its sequence number.
+    pub fn read(&mut self) -> Result<(u64, T), Error> {
+        self.buffer.read()
+    }
+
+    // Iterate over the elements with their sequence numbers, from the oldest to the newest.
+    pub fn iter_with_seq(&self) -> impl DoubleEndedIterator<Item = (u64, &T)> + ExactSizeIterator {
+        self.buffer.iter().map(|(seq, element)| (*seq, element))
+    }
+
+    // Return the sequence number of the last element ever written, even if it was read since.
+    pub fn last_seq(&self) -> Option<u64> {
+        self.next_seq.checked_sub(1)
+    }
+
+    // Return the sequence number of the oldest stored element.
+    pub fn first_seq(&self) -> Option<u64> {
+        self.buffer.front().map(|(seq, _)| *seq)
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.buffer.is_full()
+    }
+
+    // Remove every element. The sequence numbers keep counting up from where they were.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elements_are_numbered_in_write_order() {
+        let mut buffer = SeqBuffer::new(2);
+        assert_eq!(None, buffer.last_seq());
+        assert_eq!(Ok(0), buffer.write('a'));
+        assert_eq!(Ok(1), buffer.write('b'));
+        assert_eq!(Err(WriteError('c')), buffer.write('c'));
+        assert_eq!(Ok((0, 'a')), buffer.read());
+        assert_eq!(Ok(2), buffer.write('c'));
+        assert_eq!(
+            vec![(1, &'b'), (2, &'c')],
+            buffer.iter_with_seq().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(2), buffer.last_seq());
+    }
+
+    #[test]
+    fn gaps_in_the_numbers_reveal_overwritten_elements() {
+        let mut buffer = SeqBuffer::new(3);
+        let mut last_seen = None;
+        for i in 0..3 {
+            buffer.overwrite(i);
+        }
+        while let Ok((seq, _)) = buffer.read() {
+            last_seen = Some(seq);
+        }
+
+        for i in 3..8 {
+            buffer.overwrite(i);
+        }
+        let lost = buffer.first_seq().unwrap() - last_seen.unwrap() - 1;
+        assert_eq!(2, lost);
+
+        buffer.clear();
+        assert_eq!((8, None), buffer.overwrite(8));
+    }
+}
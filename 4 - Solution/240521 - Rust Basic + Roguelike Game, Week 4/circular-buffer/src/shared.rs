@@ -0,0 +1,134 @@
+use crate::{CircularBuffer, Error, WriteError};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+// A handle to a CircularBuffer shared by every clone of it, which can be used from any thread.
+//
+// Unlike `sync::CircularBuffer`, no operation blocks waiting for elements or free slots.
+pub struct SharedCircularBuffer<T> {
+    buffer: Arc<Mutex<CircularBuffer<T>>>,
+}
+
+impl<T> SharedCircularBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        CircularBuffer::new(capacity).into()
+    }
+
+    pub fn write(&self, element: T) -> Result<(), WriteError<T>> {
+        self.lock().write(element)
+    }
+
+    // Write the element according to the overflow policy, like `CircularBuffer::push`.
+    pub fn push(&self, element: T) -> Result<Option<T>, WriteError<T>> {
+        self.lock().push(element)
+    }
+
+    pub fn overwrite(&self, element: T) -> Option<T> {
+        self.lock().overwrite(element)
+    }
+
+    pub fn read(&self) -> Result<T, Error> {
+        self.lock().read()
+    }
+
+    // Clone the stored elements into a Vec, from the oldest to the newest.
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.lock().to_vec()
+    }
+
+    // Run `f` on the buffer, which no other handle can access in the meantime.
+    //
+    // If `f` panics, the lock is not treated as poisoned: the other handles keep using
+    // the buffer, with whatever changes `f` made before panicking.
+    pub fn with<R>(&self, f: impl FnOnce(&mut CircularBuffer<T>) -> R) -> R {
+        f(&mut self.lock())
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lock().capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.lock().is_full()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CircularBuffer<T>> {
+        crate::lock(&self.buffer)
+    }
+}
+
+// Cloning only creates another handle to the same buffer.
+impl<T> Clone for SharedCircularBuffer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: Arc::clone(&self.buffer),
+        }
+    }
+}
+
+// Share an existing buffer, keeping its contents and overflow policy.
+impl<T> From<CircularBuffer<T>> for SharedCircularBuffer<T> {
+    fn from(buffer: CircularBuffer<T>) -> Self {
+        Self {
+            buffer: Arc::new(Mutex::new(buffer)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OverflowPolicy;
+    use std::thread;
+
+    #[test]
+    fn clones_share_the_same_buffer() {
+        let log = SharedCircularBuffer::new(2);
+        let other = log.clone();
+        assert!(log.write('1').is_ok());
+        assert!(other.write('2').is_ok());
+        assert_eq!(Some('1'), other.overwrite('3'));
+        assert_eq!(vec!['2', '3'], log.to_vec());
+        assert_eq!(Ok('2'), other.read());
+        assert_eq!(1, log.with(|buffer| buffer.len()));
+    }
+
+    #[test]
+    fn handles_can_append_from_several_threads() {
+        let log = SharedCircularBuffer::from(CircularBuffer::with_overflow_policy(
+            10,
+            OverflowPolicy::Overwrite,
+        ));
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let log = log.clone();
+                thread::spawn(move || {
+                    for j in 0..100 {
+                        log.push(i * 100 + j).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert!(log.is_full());
+        assert_eq!(400, log.with(|buffer| buffer.stats().writes));
+    }
+}
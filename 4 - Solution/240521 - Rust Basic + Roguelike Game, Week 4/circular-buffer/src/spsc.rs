@@ -0,0 +1,219 @@
+// A lock-free CircularBuffer for exactly one producer thread and one consumer thread.
+use crate::{Error, WriteError};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// The state shared by a Producer and its Consumer.
+//
+// One slot more than the capacity is allocated, so that a full buffer (`end_idx`
+// right behind `start_idx`) can be told apart from an empty one (equal indices).
+struct Shared<T> {
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // Only stored by the Consumer.
+    start_idx: AtomicUsize,
+    // Only stored by the Producer.
+    end_idx: AtomicUsize,
+}
+
+// SAFETY: a slot is accessed either by the Producer, before it publishes it through
+// `end_idx`, or by the Consumer, before it releases it through `start_idx`, never by both.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn next_idx(&self, idx: usize) -> usize {
+        (idx + 1) % self.buffer.len()
+    }
+
+    fn len(&self) -> usize {
+        let start_idx = self.start_idx.load(Ordering::Acquire);
+        let end_idx = self.end_idx.load(Ordering::Acquire);
+        (end_idx + self.buffer.len() - start_idx) % self.buffer.len()
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let mut idx = *self.start_idx.get_mut();
+        let end_idx = *self.end_idx.get_mut();
+        while idx != end_idx {
+            // SAFETY: the slots from `start_idx` up to `end_idx` are initialized,
+            // and both halves are gone.
+            unsafe { self.buffer[idx].get_mut().assume_init_drop() };
+            idx = self.next_idx(idx);
+        }
+    }
+}
+
+// A CircularBuffer that has to be split into a Producer and a Consumer to be used.
+pub struct CircularBuffer<T> {
+    shared: Shared<T>,
+}
+
+impl<T> CircularBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            shared: Shared {
+                buffer: (0..=capacity)
+                    .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                    .collect(),
+                start_idx: AtomicUsize::new(0),
+                end_idx: AtomicUsize::new(0),
+            },
+        }
+    }
+
+    // Split the buffer into its writing and reading halves, which can be sent to different threads.
+    pub fn split(self) -> (Producer<T>, Consumer<T>) {
+        let shared = Arc::new(self.shared);
+
+        (
+            Producer {
+                shared: Arc::clone(&shared),
+            },
+            Consumer { shared },
+        )
+    }
+}
+
+// The writing half of an spsc::CircularBuffer.
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Producer<T> {
+    pub fn write(&mut self, element: T) -> Result<(), WriteError<T>> {
+        let end_idx = self.shared.end_idx.load(Ordering::Relaxed);
+        let next_idx = self.shared.next_idx(end_idx);
+        if next_idx == self.shared.start_idx.load(Ordering::Acquire) {
+            return Err(WriteError(element));
+        }
+
+        // SAFETY: the slot at `end_idx` is free and the Consumer does not touch it
+        // until `end_idx` is moved past it below.
+        unsafe { (*self.shared.buffer[end_idx].get()).write(element) };
+        self.shared.end_idx.store(next_idx, Ordering::Release);
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.buffer.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+}
+
+// The reading half of an spsc::CircularBuffer.
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Consumer<T> {
+    pub fn read(&mut self) -> Result<T, Error> {
+        let start_idx = self.shared.start_idx.load(Ordering::Relaxed);
+        if start_idx == self.shared.end_idx.load(Ordering::Acquire) {
+            return Err(Error::EmptyBuffer);
+        }
+
+        // SAFETY: the slot at `start_idx` was initialized by the Producer, and it does
+        // not touch it again until `start_idx` is moved past it below.
+        let element = unsafe { (*self.shared.buffer[start_idx].get()).assume_init_read() };
+        self.shared
+            .start_idx
+            .store(self.shared.next_idx(start_idx), Ordering::Release);
+
+        Ok(element)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.buffer.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn items_are_read_in_the_order_they_are_written() {
+        let (mut producer, mut consumer) = CircularBuffer::new(2).split();
+        assert_eq!(Err(Error::EmptyBuffer), consumer.read());
+        assert!(producer.write('1').is_ok());
+        assert!(producer.write('2').is_ok());
+        assert!(producer.is_full());
+        assert_eq!(Err(WriteError('3')), producer.write('3'));
+        assert_eq!(Ok('1'), consumer.read());
+        assert!(producer.write('3').is_ok());
+        assert_eq!(2, consumer.len());
+        assert_eq!(Ok('2'), consumer.read());
+        assert_eq!(Ok('3'), consumer.read());
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn halves_can_be_moved_to_other_threads() {
+        let (mut producer, mut consumer) = CircularBuffer::new(4).split();
+
+        let writer = thread::spawn(move || {
+            for i in 0..1000 {
+                let mut element = i;
+                while let Err(WriteError(rejected)) = producer.write(element) {
+                    element = rejected;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < 1000 {
+            match consumer.read() {
+                Ok(element) => {
+                    assert_eq!(expected, element);
+                    expected += 1;
+                }
+                Err(_) => thread::yield_now(),
+            }
+        }
+
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn dropping_both_halves_frees_up_the_elements() {
+        let element = Arc::new(());
+        let (mut producer, consumer) = CircularBuffer::new(2).split();
+        assert!(producer.write(Arc::clone(&element)).is_ok());
+        assert!(producer.write(Arc::clone(&element)).is_ok());
+        drop(producer);
+        assert_eq!(Arc::strong_count(&element), 3);
+        drop(consumer);
+        assert_eq!(Arc::strong_count(&element), 1);
+    }
+}
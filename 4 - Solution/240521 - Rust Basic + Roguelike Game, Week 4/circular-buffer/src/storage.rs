@@ -0,0 +1,43 @@
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+
+/// The slots a CircularBuffer keeps its elements in: an owned Vec, an array, or a borrowed slice.
+///
+/// The buffer keeps track of which slots are initialized, so the storage never drops
+/// the elements itself.
+///
+/// # Safety
+///
+/// The buffer reads the slots it initialized through unsafe code, so `as_ref` and `as_mut`
+/// must return the same slots, with the same length, on every call, and a storage returned
+/// by `allocate(capacity)` must have exactly `capacity` slots.
+pub unsafe trait Storage<T>: AsRef<[MaybeUninit<T>]> + AsMut<[MaybeUninit<T>]> {
+    // Whether `allocate` can return a new storage. Buffers can only grow or be resized if so.
+    const CAN_GROW: bool = false;
+
+    // Allocate a new storage with `capacity` slots, or return `None` if `CAN_GROW` is false.
+    fn allocate(capacity: usize) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let _ = capacity;
+        None
+    }
+}
+
+// SAFETY: a Vec derefs to the same slots until it is modified, which only the buffer does.
+unsafe impl<T> Storage<T> for Vec<MaybeUninit<T>> {
+    const CAN_GROW: bool = true;
+
+    fn allocate(capacity: usize) -> Option<Self> {
+        let mut buffer = Vec::with_capacity(capacity);
+        buffer.resize_with(capacity, MaybeUninit::uninit);
+        Some(buffer)
+    }
+}
+
+// SAFETY: an array is its own slots.
+unsafe impl<T, const N: usize> Storage<T> for [MaybeUninit<T>; N] {}
+
+// SAFETY: the borrowed slice always refers to the same slots.
+unsafe impl<T> Storage<T> for &mut [MaybeUninit<T>] {}
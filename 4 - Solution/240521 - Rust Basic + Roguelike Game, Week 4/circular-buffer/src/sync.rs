@@ -0,0 +1,170 @@
+// A thread-safe CircularBuffer whose operations block until they can complete.
+use crate::{Error, WriteError};
+use std::fmt;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+// The error returned by `read_timeout` when no element became available in time.
+#[derive(Debug, PartialEq)]
+pub struct Timeout;
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out waiting on a circular buffer")
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+pub struct CircularBuffer<T> {
+    buffer: Mutex<crate::CircularBuffer<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> CircularBuffer<T> {
+    // A buffer with a capacity of 0 cannot store anything: `write` and `read` block forever,
+    // and only `try_write`, `try_read` and `read_timeout` return, with an error.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: Mutex::new(crate::CircularBuffer::new(capacity)),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    // Write the element, waiting until the buffer has space for it.
+    pub fn write(&self, element: T) {
+        let mut buffer = self
+            .not_full
+            .wait_while(self.lock(), |buffer| buffer.is_full())
+            .unwrap_or_else(PoisonError::into_inner);
+        buffer.push_end(element);
+        drop(buffer);
+
+        self.not_empty.notify_one();
+    }
+
+    // Read the oldest element, waiting until one is available.
+    pub fn read(&self) -> T {
+        let mut buffer = self
+            .not_empty
+            .wait_while(self.lock(), |buffer| buffer.is_empty())
+            .unwrap_or_else(PoisonError::into_inner);
+        let element = buffer.pop_start();
+        drop(buffer);
+
+        self.not_full.notify_one();
+        element
+    }
+
+    // Read the oldest element, waiting at most `timeout` for one to be available.
+    pub fn read_timeout(&self, timeout: Duration) -> Result<T, Timeout> {
+        let (mut buffer, _) = self
+            .not_empty
+            .wait_timeout_while(self.lock(), timeout, |buffer| buffer.is_empty())
+            .unwrap_or_else(PoisonError::into_inner);
+        let element = buffer.read().map_err(|_| Timeout)?;
+        drop(buffer);
+
+        self.not_full.notify_one();
+        Ok(element)
+    }
+
+    pub fn try_write(&self, element: T) -> Result<(), WriteError<T>> {
+        self.lock().write(element)?;
+        self.not_empty.notify_one();
+
+        Ok(())
+    }
+
+    pub fn try_read(&self) -> Result<T, Error> {
+        let element = self.lock().read()?;
+        self.not_full.notify_one();
+
+        Ok(element)
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+        self.not_full.notify_all();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lock().capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.lock().is_full()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, crate::CircularBuffer<T>> {
+        crate::lock(&self.buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn try_variants_do_not_block() {
+        let buffer = CircularBuffer::new(1);
+        assert_eq!(Err(Error::EmptyBuffer), buffer.try_read());
+        assert!(buffer.try_write('1').is_ok());
+        assert_eq!(Err(WriteError('2')), buffer.try_write('2'));
+        assert_eq!(Ok('1'), buffer.try_read());
+    }
+
+    #[test]
+    fn write_blocks_until_a_slot_is_free() {
+        let buffer = Arc::new(CircularBuffer::new(2));
+
+        let writer = {
+            let buffer = Arc::clone(&buffer);
+            thread::spawn(move || {
+                for i in 0..100 {
+                    buffer.write(i);
+                }
+            })
+        };
+
+        for i in 0..100 {
+            assert_eq!(i, buffer.read());
+        }
+
+        writer.join().unwrap();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn read_timeout_gives_up_on_an_empty_buffer() {
+        let buffer = Arc::new(CircularBuffer::new(1));
+        assert_eq!(Err(Timeout), buffer.read_timeout(Duration::from_millis(10)));
+
+        let writer = {
+            let buffer = Arc::clone(&buffer);
+            thread::spawn(move || buffer.write('1'))
+        };
+        assert_eq!(Ok('1'), buffer.read_timeout(Duration::from_secs(10)));
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn zero_capacity_buffer_fails_without_blocking() {
+        let buffer = CircularBuffer::new(0);
+        assert_eq!(Err(WriteError('1')), buffer.try_write('1'));
+        assert_eq!(Err(Error::EmptyBuffer), buffer.try_read());
+        assert_eq!(Err(Timeout), buffer.read_timeout(Duration::from_millis(1)));
+    }
+}
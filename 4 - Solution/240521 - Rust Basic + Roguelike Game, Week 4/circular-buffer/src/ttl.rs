@@ -0,0 +1,162 @@
+use crate::{CircularBuffer, Error, WriteError};
+use std::time::{Duration, Instant};
+
+// A CircularBuffer whose elements expire once they are older than a time to live,
+// for example transient status messages.
+//
+// Every element is timestamped when it is written. Expired elements are skipped by `iter`
+// and `len`, and dropped by the methods taking `&mut self`.
+pub struct TtlBuffer<T> {
+    buffer: CircularBuffer<(Instant, T)>,
+    ttl: Duration,
+}
+
+impl<T> TtlBuffer<T> {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            buffer: CircularBuffer::new(capacity),
+            ttl,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    // Write the element, after dropping the expired ones to make room.
+    pub fn write(&mut self, element: T) -> Result<(), WriteError<T>> {
+        self.write_at(Instant::now(), element)
+    }
+
+    // Write the element, after dropping the expired ones, and evict the oldest element
+    // if the buffer is still full.
+    pub fn overwrite(&mut self, element: T) -> Option<T> {
+        self.overwrite_at(Instant::now(), element)
+    }
+
+    // Read the oldest element that has not expired, dropping the expired ones.
+    pub fn read(&mut self) -> Result<T, Error> {
+        self.read_at(Instant::now())
+    }
+
+    // Iterate over the elements that have not expired, from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.iter_at(Instant::now())
+    }
+
+    // Drop the expired elements and return how many there were.
+    pub fn expire(&mut self) -> usize {
+        self.expire_at(Instant::now())
+    }
+
+    // Return the number of elements that have not expired.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    fn is_expired(&self, written: Instant, now: Instant) -> bool {
+        now.duration_since(written) > self.ttl
+    }
+
+    fn write_at(&mut self, now: Instant, element: T) -> Result<(), WriteError<T>> {
+        self.expire_at(now);
+        self.buffer
+            .write((now, element))
+            .map_err(|WriteError((_, element))| WriteError(element))
+    }
+
+    fn overwrite_at(&mut self, now: Instant, element: T) -> Option<T> {
+        self.expire_at(now);
+        self.buffer
+            .overwrite((now, element))
+            .map(|(_, element)| element)
+    }
+
+    fn read_at(&mut self, now: Instant) -> Result<T, Error> {
+        self.expire_at(now);
+        self.buffer.read().map(|(_, element)| element)
+    }
+
+    // The elements are written in chronological order, so the expired ones are the oldest.
+    fn iter_at(&self, now: Instant) -> impl Iterator<Item = &T> {
+        self.buffer
+            .iter()
+            .skip_while(move |(written, _)| self.is_expired(*written, now))
+            .map(|(_, element)| element)
+    }
+
+    fn expire_at(&mut self, now: Instant) -> usize {
+        let mut count = 0;
+        while let Some(&(written, _)) = self.buffer.front() {
+            if !self.is_expired(written, now) {
+                break;
+            }
+            self.buffer.read().unwrap();
+            count += 1;
+        }
+
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(3);
+
+    #[test]
+    fn expired_elements_are_skipped_and_dropped() {
+        let start = Instant::now();
+        let mut buffer = TtlBuffer::new(3, TTL);
+        assert!(buffer.write_at(start, '1').is_ok());
+        assert!(buffer.write_at(start + Duration::from_secs(2), '2').is_ok());
+
+        let later = start + Duration::from_secs(4);
+        assert_eq!(vec![&'2'], buffer.iter_at(later).collect::<Vec<_>>());
+        assert_eq!(Ok('2'), buffer.read_at(later));
+        assert_eq!(
+            Err(Error::EmptyBuffer),
+            buffer.read_at(start + Duration::from_secs(6))
+        );
+    }
+
+    #[test]
+    fn expired_elements_make_room_for_writes() {
+        let start = Instant::now();
+        let mut buffer = TtlBuffer::new(2, TTL);
+        assert!(buffer.write_at(start, '1').is_ok());
+        assert!(buffer.write_at(start, '2').is_ok());
+        assert_eq!(Err(WriteError('3')), buffer.write_at(start, '3'));
+        assert_eq!(Some('1'), buffer.overwrite_at(start, '3'));
+
+        assert!(buffer.write_at(start + TTL * 2, '4').is_ok());
+        assert_eq!(0, buffer.expire_at(start + TTL * 2));
+        assert_eq!(
+            vec![&'4'],
+            buffer.iter_at(start + TTL * 2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn fresh_elements_are_kept() {
+        let mut buffer = TtlBuffer::new(2, Duration::from_secs(60));
+        assert!(buffer.write('1').is_ok());
+        assert_eq!(0, buffer.expire());
+        assert_eq!(1, buffer.len());
+        assert_eq!(Ok('1'), buffer.read());
+        assert!(buffer.is_empty());
+    }
+}